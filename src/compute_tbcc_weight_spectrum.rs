@@ -0,0 +1,64 @@
+use std::num::ParseIntError;
+
+use crate::trellis::trellis::Trellis;
+use crate::poly_wrapper::{PolyWrapper, BigUintWrapper, WrappedToPoly, WrappedToBigUint};
+use crate::compute_ztcc_weight_spectrum::compute_transfer_function;
+use crate::compute_ztcc_weight_spectrum_fast::power_by_squaring;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+//   This function computes the exact weight spectrum of a given tail-biting
+//   convolutional code (TBCC) of length N, with arbitrary-precision coefficients
+
+//   Inputs:
+//       1) N: the trellis length
+//       2) trell: the trellis of the TBCC (any number of input rails k)
+
+//   Outputs: the weight enumerating function of the TBCC, i.e. the trace of
+//       the N-th power of the transfer matrix. Diagonal entry (s, s) counts the
+//       length-N paths that start and end in state s, so the trace counts every
+//       tail-biting path once. Index 'i' represents weight 'i'.
+
+pub fn compute_tbcc_weight_spectrum(trellis_len: u16, trell: Trellis) -> Result<Vec<BigUint>, ParseIntError> {
+
+    let num_states = trell.num_states;
+    println!("Step 1: Compute the transfer function");
+    let transfer_function = compute_transfer_function(&trell);
+
+    println!("Step 2: raise the transfer function to the N-th power by repeated squaring.");
+    let power = power_by_squaring(&transfer_function, u64::from(trellis_len));
+
+    // Step 3: Sum the weight enumerating functions of the closed paths of every state
+    println!("Step 3: Compute the overall weight enumerating function.");
+
+    let mut trace: PolyWrapper<BigUintWrapper> = PolyWrapper::zero();
+    for state in 0..num_states {
+        trace += power[(state, state)].clone();
+    }
+
+    Ok(trace.unwrap().coeffs().into_iter().map(|coefficient| coefficient.unwrap()).collect())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compute_tbcc_weight_spectrum::compute_tbcc_weight_spectrum;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::vec2d::Vec2d;
+
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_every_input_is_a_tail_biting_codeword() {
+        // each of the 2^N inputs of a feedforward encoder has exactly one tail-biting path
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let weight_spectrum = compute_tbcc_weight_spectrum(8, trellis).unwrap();
+        let total: BigUint = weight_spectrum.iter().sum();
+        assert_eq!(total, BigUint::from(256_u32));
+        assert_eq!(weight_spectrum[0], BigUint::from(1_u32));
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let total: BigUint = compute_tbcc_weight_spectrum(7, trellis).unwrap().iter().sum();
+        assert_eq!(total, BigUint::from(128_u32));
+    }
+}
@@ -1,81 +1,269 @@
-
-
 use std::num::ParseIntError;
 
 use crate::trellis::trellis::Trellis;
-use crate::poly_wrapper::{PolyWrapper, PolyToWrapped, WrappedToPoly};
+use crate::poly_wrapper::{PolyWrapper, PolyToWrapped, WrappedToPoly, WrappedToBigUint};
 use crate::poly_wrapper::BigUintWrapper;
+use crate::truncated_poly::TruncatedPoly;
+use crate::compute_ztcc_weight_spectrum_fast::{propagate_weight_enumerators, propagate_weight_enumerators_by_length, power_entry_by_squaring};
 use polynomen::One;
 use polynomen::Zero;
 
 use nalgebra::DMatrix;
+use num_bigint::BigUint;
+use num_traits::{FromPrimitive, ToPrimitive};
 use polynomen::{Poly, poly};
 
-//   This function computes the weight spectrum of a given high-rate ZTCC
-//   of length N
+use serde::{Deserialize, Serialize};
+
+//   This function computes the exact weight spectrum of a given high-rate ZTCC
+//   of length N, with arbitrary-precision coefficients
 
 //   Inputs:
-//       1) v-1: the overall constraint length
-//       2) numerators: conventional octal form of numerators in 1st column
-//       3) denominator: conventional octal form of den. in 1st column
-//       4) N: the trellis length
+//       1) N: the trellis length
+//       2) trell: the trellis of the ZTCC (any number of input rails k)
 
-//   Outputs: weight_node, a struct that includes
-//       1) weight_spectrum: a (d_max+1)-by-1 column vector denoting the #
-//           codewords of weight i. Index 'i' represents weight 'i-1'.
-//       2) overall_weight_function: a polynomial representation of WEF
+//   Outputs: weight_spectrum, a (d_max+1)-by-1 vector denoting the # codewords of
+//       weight i. Index 'i' represents weight 'i' (unlike the MATLAB version).
 
 //   Written by Hengjie Yang (hengjie.yang@ucla.edu) 03/30/21
 //   Translated into Rust by Holden Grissett (holdengs@g.ucla.edu) 08/15/2022
 
-pub fn compute_ztcc_weight_spectrum(_v: u16, _numerators: Vec<u16>, _denominator: u16, trellis_len: u16, trell: Trellis) -> Result<Poly<BigUintWrapper>, ParseIntError> {
+pub fn compute_ztcc_weight_spectrum(trellis_len: u16, trell: Trellis) -> Result<Vec<BigUint>, ParseIntError> {
+
+    Ok(propagate_weight_enumerators(trellis_len, &trell, usize::MAX))
+}
+
+// The same weight spectrum as compute_ztcc_weight_spectrum, from the N-th power of the transfer
+// matrix by repeated squaring, for very large N
+pub fn compute_ztcc_weight_spectrum_by_squaring(trellis_len: u16, trell: Trellis) -> Result<Vec<BigUint>, ParseIntError> {
 
-    let num_states = trell.num_states;
-    let mut transfer_function: DMatrix<PolyWrapper<BigUintWrapper>> = DMatrix::zeros(num_states, num_states);
-    let mut tmp;
-    let mut test_vec: Vec<Vec<i16>> = vec![vec![-1; num_states]; num_states];
     println!("Step 1: Compute the transfer function");
+    let transfer_function = compute_transfer_function(&trell);
+
+    println!("Step 2: raise the transfer function to the N-th power by repeated squaring.");
+    let ret: Poly<BigUintWrapper> = power_entry_by_squaring(&transfer_function, u64::from(trellis_len)).unwrap();
+    Ok(ret.coeffs().into_iter().map(|coefficient| coefficient.unwrap()).collect())
+}
+
+// Weight spectra of the ZTCCs of every trellis length from 1 to N, as written to
+// weight_spectrum_table.json: spectra[i] belongs to trellis_lengths[i] = i + 1 (mu of those
+// sections being the termination), with every coefficient a decimal string
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeightSpectrumTable {
+    pub trellis_lengths: Vec<u16>,
+    #[serde(with = "exact_spectra")]
+    pub spectra: Vec<Vec<BigUint>>,
+}
+
+// compute_ztcc_weight_spectrum (or compute_ztcc_weight_spectrum_truncated, with max_distance) for
+// every trellis length up to N at once, for the price of the length-N spectrum
+pub fn compute_ztcc_weight_spectrum_table(trellis_len: u16, trell: Trellis, max_distance: usize) -> Result<WeightSpectrumTable, ParseIntError> {
+
+    Ok(WeightSpectrumTable {
+        trellis_lengths: (1..=trellis_len).collect(),
+        spectra: propagate_weight_enumerators_by_length(trellis_len, &trell, max_distance),
+    })
+}
+
+// The coefficients of compute_ztcc_weight_spectrum up to weight max_distance only (all the DSO CRC
+// search needs), in time and memory that grow with max_distance instead of n*N
+pub fn compute_ztcc_weight_spectrum_truncated(trellis_len: u16, trell: Trellis, max_distance: usize) -> Result<Vec<BigUint>, ParseIntError> {
+
+    Ok(propagate_weight_enumerators(trellis_len, &trell, max_distance))
+}
+
+// compute_ztcc_weight_spectrum_truncated by repeated squaring of the transfer matrix, whose entries
+// are truncated to degree max_distance, so even the squares of long trellises stay small
+pub fn compute_ztcc_weight_spectrum_truncated_by_squaring(trellis_len: u16, trell: Trellis, max_distance: usize) -> Result<Vec<BigUint>, ParseIntError> {
+
+    println!("Step 1: Compute the truncated transfer function");
+    let num_states = trell.num_states;
+    let mut transfer_function: DMatrix<TruncatedPoly<BigUint>> = DMatrix::zeros(num_states, num_states);
     for current_state in 0..trell.num_states {
         for input in 0..trell.num_input_symbols {
-            let next_state = trell.next_states.index(current_state, input);
-            let output = u16::from(*trell.outputs.index(current_state, input));
-            tmp = usize::from(*next_state);
-            transfer_function[(current_state, usize::from(*next_state))] = calc_polynomial_from_weight(output.count_ones()); // need to make polynomial conversion function
-            test_vec[current_state][tmp] = output.count_ones() as i16;
-            if current_state == 0 && tmp == 16 {
-                println!("{}", output);
+            let next_state = *trell.next_states.index(current_state, input);
+            let weight = trell.outputs.index(current_state, input).count_ones() as usize;
+            transfer_function[(current_state, next_state)] += TruncatedPoly::monomial(weight, max_distance);
+        }
+    }
+
+    println!("Step 2: raise the transfer function to the N-th power by repeated squaring.");
+    Ok(power_entry_by_squaring(&transfer_function, u64::from(trellis_len)).into_coeffs())
+}
+
+//   This function computes the input-output weight enumerator (IOWE) of a ZTCC of length N,
+//   A(I, D) = sum of I^w D^d over the ZTCC codewords of input weight w and output weight d,
+//   by the same row-vector propagation as compute_ztcc_weight_spectrum with every branch
+//   weighted by I^(weight of its input symbol) D^(weight of its output)
+
+//   Inputs:
+//       1) N: the trellis length
+//       2) trell: the trellis of the ZTCC (any number of input rails k)
+//       3) max_distance: output weights above it are dropped (usize::MAX for the whole IOWE)
+
+//   Outputs: iowe, with iowe[w][d] the # codewords of input weight w and output weight d.
+//       The input weight counts the k*(N - mu) message (and CRC) bits and not the termination
+//       inputs. For k > 1 several termination sequences may lead back to state 0; as in
+//       compute_ztcc_weight_spectrum, each of them is counted as a codeword of its own, so
+//       summing over w gives the weight spectrum.
+pub fn compute_ztcc_iowe(trellis_len: u16, trell: Trellis, max_distance: usize) -> Result<Vec<Vec<BigUint>>, ParseIntError> {
+
+    let num_states = trell.num_states;
+    let k = trell.num_input_rails;
+    let n = trell.num_output_symbols.trailing_zeros() as usize;
+    let num_message_sections = usize::from(trellis_len).saturating_sub(trell.termination_length);
+    let max_input_weight = k * num_message_sections;
+    let max_weight = (n * usize::from(trellis_len)).min(max_distance);
+
+    println!("Step 1: propagate the input-output weight enumerating functions from state 0.");
+    let zeros = vec![vec![BigUint::from(0_u32); max_weight + 1]; max_input_weight + 1];
+    let mut row: Vec<Vec<Vec<BigUint>>> = vec![zeros.clone(); num_states];
+    row[0][0][0] = BigUint::from(1_u32);
+    for section in 0..usize::from(trellis_len) {
+        println!("Current depths: {}", section);
+        let mut next_row: Vec<Vec<Vec<BigUint>>> = vec![zeros.clone(); num_states];
+        for (state, enumerator) in row.iter().enumerate() {
+            for input in 0..trell.num_input_symbols {
+                let next_state = *trell.next_states.index(state, input);
+                let weight = trell.outputs.index(state, input).count_ones() as usize;
+                let input_weight = if section < num_message_sections { input.count_ones() as usize } else { 0 };
+                if weight > max_weight {
+                    continue;
+                }
+                for (w, coefficients) in enumerator[..=(max_input_weight - input_weight)].iter().enumerate() {
+                    for (d, coefficient) in coefficients[..=(max_weight - weight)].iter().enumerate() {
+                        if !num_traits::Zero::is_zero(coefficient) {
+                            next_row[next_state][w + input_weight][d + weight] += coefficient;
+                        }
+                    }
+                }
             }
         }
+        row = next_row;
+    }
+
+    println!("Step 2: Compute the overall input-output weight enumerating function.");
+    let mut iowe = row.swap_remove(0);
+    // drop the input and output weights that no codeword has, from the top
+    while iowe.len() > 1 && iowe.last().is_some_and(|coefficients| coefficients.iter().all(num_traits::Zero::is_zero)) {
+        iowe.pop();
+    }
+    while iowe[0].len() > 1 && iowe.iter().all(|coefficients| coefficients.last().is_some_and(num_traits::Zero::is_zero)) {
+        for coefficients in iowe.iter_mut() {
+            coefficients.pop();
+        }
     }
+    Ok(iowe)
+}
+
+// Builds the one-step transfer matrix of the trellis, whose entry (s, s') is the sum of D^w over the
+// branches from state s to state s' with output weight w
+pub fn compute_transfer_function(trell: &Trellis) -> DMatrix<PolyWrapper<BigUintWrapper>> {
 
-    println!("Step 2: compute the weight enumerating function for each starting state.");
-    print_matrix(test_vec);
-    let mut identity_matrix: DMatrix<PolyWrapper<BigUintWrapper>> = DMatrix::<PolyWrapper<BigUintWrapper>>::identity(num_states, num_states);
-    for i in 0..trellis_len {
-        println!("Current depths: {}", i);
-        identity_matrix = identity_matrix * &transfer_function;
+    let num_states = trell.num_states;
+    let mut transfer_function: DMatrix<PolyWrapper<BigUintWrapper>> = DMatrix::zeros(num_states, num_states);
+    for current_state in 0..trell.num_states {
+        for input in 0..trell.num_input_symbols {
+            let next_state = *trell.next_states.index(current_state, input);
+            let output = *trell.outputs.index(current_state, input);
+            // parallel branches between the same pair of states add up
+            transfer_function[(current_state, next_state)] += calc_polynomial_from_weight(output.count_ones());
+        }
     }
 
-    // Step 3: Compute the overall weight enumerating function for finite-length ZTCC
-    println!("Step 3: Compute the overall weight enumerating function.");
+    transfer_function
+}
 
-    let ret: Poly<BigUintWrapper> = identity_matrix[(0, 0)].clone().unwrap();
-    Ok(ret)
+// Writes a weight spectrum as a JSON array of decimal strings, which keeps every coefficient exact
+// (JSON readers commonly parse numbers as f64, which is only exact up to 2^53)
+pub fn weight_spectrum_to_json(weight_spectrum: &[BigUint]) -> String {
+    serde_json::to_string(&decimal_strings(weight_spectrum)).expect("couldn't serialize weight spectrum to json")
 }
 
-fn print_matrix(matrix: Vec<Vec<i16>>) -> () {
-    for row in matrix {
-        for e in row {
-            if e == -1 {
-                print!("_ ");
-            } else {
-                print!("{} ", e);
-            }
-        }
-        println!();
+// Reads a weight spectrum written by weight_spectrum_to_json. Arrays of plain numbers, as written by
+// the f64 spectrum, are accepted too, but their large coefficients are only approximate.
+pub fn weight_spectrum_from_json(json: &str) -> Result<Vec<BigUint>, serde_json::Error> {
+    let coefficients: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    coefficients.iter().map(parse_coefficient).collect()
+}
+
+// Writes an IOWE as a JSON array (one per input weight) of arrays of decimal strings
+pub fn iowe_to_json(iowe: &[Vec<BigUint>]) -> String {
+    let rows: Vec<Vec<String>> = iowe.iter().map(|coefficients| decimal_strings(coefficients)).collect();
+    serde_json::to_string(&rows).expect("couldn't serialize iowe to json")
+}
+
+// Reads an IOWE written by iowe_to_json
+pub fn iowe_from_json(json: &str) -> Result<Vec<Vec<BigUint>>, serde_json::Error> {
+    let rows: Vec<Vec<serde_json::Value>> = serde_json::from_str(json)?;
+    rows.iter().map(|coefficients| coefficients.iter().map(parse_coefficient).collect()).collect()
+}
+
+// Whether an IOWE summed over the input weights gives the weight spectrum, i.e. whether both were
+// computed for the same code, trellis length and truncation
+pub fn iowe_matches_weight_spectrum(iowe: &[Vec<BigUint>], weight_spectrum: &[BigUint]) -> bool {
+    let len = iowe.iter().map(|coefficients| coefficients.len()).max().unwrap_or(0).max(weight_spectrum.len());
+    (0..len).all(|d| {
+        let marginal: BigUint = iowe.iter().filter_map(|coefficients| coefficients.get(d)).sum();
+        marginal == weight_spectrum.get(d).cloned().unwrap_or_default()
+    })
+}
+
+// Serde adapter writing a weight spectrum field the same way as weight_spectrum_to_json,
+// for use with #[serde(with = "crate::compute_ztcc_weight_spectrum::exact_spectrum")]
+pub mod exact_spectrum {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(weight_spectrum: &[BigUint], serializer: S) -> Result<S::Ok, S::Error> {
+        super::decimal_strings(weight_spectrum).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<BigUint>, D::Error> {
+        let coefficients: Vec<serde_json::Value> = Vec::deserialize(deserializer)?;
+        coefficients.iter().map(|coefficient| super::parse_coefficient(coefficient).map_err(serde::de::Error::custom)).collect()
+    }
+}
+
+// exact_spectrum for a list of weight spectra
+mod exact_spectra {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(weight_spectra: &[Vec<BigUint>], serializer: S) -> Result<S::Ok, S::Error> {
+        let rows: Vec<Vec<String>> = weight_spectra.iter().map(|weight_spectrum| super::decimal_strings(weight_spectrum)).collect();
+        rows.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<BigUint>>, D::Error> {
+        let rows: Vec<Vec<serde_json::Value>> = Vec::deserialize(deserializer)?;
+        rows.iter().map(|coefficients| {
+            coefficients.iter().map(|coefficient| super::parse_coefficient(coefficient).map_err(serde::de::Error::custom)).collect()
+        }).collect()
     }
 }
 
+fn decimal_strings(weight_spectrum: &[BigUint]) -> Vec<String> {
+    weight_spectrum.iter().map(|coefficient| coefficient.to_string()).collect()
+}
+
+fn parse_coefficient(coefficient: &serde_json::Value) -> Result<BigUint, serde_json::Error> {
+    let parsed = match coefficient {
+        serde_json::Value::String(decimal) => decimal.parse::<BigUint>().ok(),
+        serde_json::Value::Number(number) => match number.as_u64() {
+            Some(integer) => Some(BigUint::from(integer)),
+            None => number.as_f64().and_then(|float| BigUint::from_f64(float.round())),
+        },
+        _ => None,
+    };
+    parsed.ok_or_else(|| serde::de::Error::custom(format!("invalid weight spectrum coefficient {}", coefficient)))
+}
+
+// The weight spectrum as f64, for the performance bounds (coefficients beyond f64 range become infinite)
+pub fn weight_spectrum_to_f64(weight_spectrum: &[BigUint]) -> Vec<f64> {
+    weight_spectrum.iter().map(|coefficient| coefficient.to_f64().unwrap_or(f64::INFINITY)).collect()
+}
+
 fn calc_polynomial_from_weight(weight: u32) -> PolyWrapper<BigUintWrapper> {
     let polynomial: PolyWrapper<BigUintWrapper>;
     if weight == 0 {
@@ -96,11 +284,106 @@ mod tests {
 
     use polynomen::{poly, Zero, One};
     use crate::{poly_wrapper::{PolyToWrapped}, compute_ztcc_weight_spectrum::calc_polynomial_from_weight};
+    use crate::compute_ztcc_weight_spectrum::{compute_ztcc_weight_spectrum, compute_ztcc_weight_spectrum_by_squaring,
+        compute_ztcc_weight_spectrum_truncated, compute_ztcc_weight_spectrum_truncated_by_squaring,
+        compute_ztcc_iowe, iowe_to_json, iowe_from_json, iowe_matches_weight_spectrum, compute_ztcc_weight_spectrum_table, WeightSpectrumTable, weight_spectrum_to_json, weight_spectrum_from_json};
+    use crate::compute_ztcc_weight_spectrum_fast::propagate_weight_enumerators;
     use crate::poly_wrapper::BigUintWrapper;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+    use num_bigint::BigUint;
 
     #[test]
     fn test_calc_polynomial_basic() {
         let polynomial = poly!(BigUintWrapper::zero(), BigUintWrapper::zero(), BigUintWrapper::one()).wrap();
         assert_eq!(calc_polynomial_from_weight(2), polynomial);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_matches_f64_spectrum() {
+        let trellis = generate_feedback_trellis(4, &[15, 17], 13).unwrap();
+        let exact = compute_ztcc_weight_spectrum(8, trellis.clone()).unwrap();
+        let approximate: Vec<f64> = propagate_weight_enumerators(8, &trellis, usize::MAX);
+        let expected: Vec<BigUint> = approximate.iter().map(|&c| BigUint::from(c as u64)).collect();
+        assert_eq!(exact, expected);
+    }
+
+    #[test]
+    fn test_squaring_matches_propagation() {
+        // parallel branches (k = 3, 4 states) and odd and even lengths
+        let trellis = generate_feedback_trellis(3, &[1, 3, 6], 7).unwrap();
+        for trellis_len in [1, 8, 13, 40] {
+            assert_eq!(compute_ztcc_weight_spectrum_by_squaring(trellis_len, trellis.clone()).unwrap(),
+                compute_ztcc_weight_spectrum(trellis_len, trellis.clone()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_truncated_spectrum_is_prefix() {
+        let trellis = generate_feedback_trellis(3, &[1, 3, 6], 7).unwrap();
+        let full = compute_ztcc_weight_spectrum(30, trellis.clone()).unwrap();
+        let truncated = compute_ztcc_weight_spectrum_truncated(30, trellis.clone(), 6).unwrap();
+        assert_eq!(truncated, full[..=6]);
+        assert_eq!(compute_ztcc_weight_spectrum_truncated_by_squaring(30, trellis, 6).unwrap(), truncated);
+    }
+
+    #[test]
+    fn test_iowe_matches_enumeration() {
+        // every 4-bit message of the (7, 5) code with 6 sections (2 for the termination)
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let iowe = compute_ztcc_iowe(6, trellis.clone(), usize::MAX).unwrap();
+        let mut expected = vec![vec![BigUint::from(0_u32); 13]; 5];
+        for m in 0..16_usize {
+            let message: BitVec = (0..4).rev().map(|j| (m >> j) & 1 == 1).collect();
+            expected[m.count_ones() as usize][encode_ztcc(&message, &trellis).count_ones()] += 1_u32;
+        }
+        let len = iowe[0].len();
+        assert!(expected.iter().all(|coefficients| coefficients[len..].iter().all(|c| *c == BigUint::from(0_u32))));
+        let expected: Vec<Vec<BigUint>> = expected.into_iter().map(|coefficients| coefficients[..len].to_vec()).collect();
+        assert_eq!(iowe, expected);
+        assert_eq!(iowe_from_json(&iowe_to_json(&iowe)).unwrap(), iowe);
+    }
+
+    #[test]
+    fn test_iowe_sums_to_weight_spectrum() {
+        let trellis = generate_feedback_trellis(4, &[15, 17], 13).unwrap();
+        let spectrum = compute_ztcc_weight_spectrum(12, trellis.clone()).unwrap();
+        let iowe = compute_ztcc_iowe(12, trellis.clone(), usize::MAX).unwrap();
+        let marginal: Vec<BigUint> = (0..spectrum.len()).map(|d| iowe.iter().map(|coefficients| &coefficients[d]).sum()).collect();
+        assert_eq!(marginal, spectrum);
+        assert!(iowe_matches_weight_spectrum(&iowe, &spectrum));
+        assert!(!iowe_matches_weight_spectrum(&iowe, &compute_ztcc_weight_spectrum(11, trellis.clone()).unwrap()));
+        let truncated = compute_ztcc_iowe(12, trellis, 5).unwrap();
+        assert!(truncated.iter().zip(&iowe).all(|(t, full)| t[..] == full[..t.len()]));
+    }
+
+    #[test]
+    fn test_table_matches_each_length() {
+        let trellis = generate_feedback_trellis(4, &[15, 17], 13).unwrap();
+        let table = compute_ztcc_weight_spectrum_table(12, trellis.clone(), usize::MAX).unwrap();
+        assert_eq!(table.trellis_lengths, (1..=12).collect::<Vec<u16>>());
+        for (&trellis_len, spectrum) in table.trellis_lengths.iter().zip(&table.spectra) {
+            assert_eq!(*spectrum, compute_ztcc_weight_spectrum(trellis_len, trellis.clone()).unwrap());
+        }
+        let truncated = compute_ztcc_weight_spectrum_table(12, trellis, 6).unwrap();
+        assert_eq!(truncated.spectra[11], compute_ztcc_weight_spectrum_truncated(12, generate_feedback_trellis(4, &[15, 17], 13).unwrap(), 6).unwrap());
+
+        let json = serde_json::to_string(&table).unwrap();
+        assert!(json.starts_with("{\"trellis_lengths\":[1,2,3,"));
+        assert_eq!(serde_json::from_str::<WeightSpectrumTable>(&json).unwrap(), table);
+    }
+
+    #[test]
+    fn test_json_round_trip_is_exact() {
+        let spectrum = vec![BigUint::from(1_u32), BigUint::from(0_u32), BigUint::from(u64::MAX) * BigUint::from(3_u32) + BigUint::from(1_u32)];
+        let json = weight_spectrum_to_json(&spectrum);
+        assert_eq!(json, "[\"1\",\"0\",\"55340232221128654846\"]");
+        assert_eq!(weight_spectrum_from_json(&json).unwrap(), spectrum);
+        assert_eq!(weight_spectrum_from_json("[1.0,0.0,1330.0]").unwrap(),
+            vec![BigUint::from(1_u32), BigUint::from(0_u32), BigUint::from(1330_u32)]);
+    }
+}
@@ -1,103 +1,112 @@
 
-
-use std::num::ParseIntError;
+use std::ops::AddAssign;
 
 use crate::trellis::trellis::Trellis;
-use crate::poly_wrapper::{PolyWrapper, PolyToWrapped, WrappedToPoly};
 
 use nalgebra::DMatrix;
-use polynomen::{Poly, poly};
-
-//   This function computes the weight spectrum of a given high-rate ZTCC
-//   of length N
-
-//   Inputs:
-//       1) v-1: the overall constraint length
-//       2) numerators: conventional octal form of numerators in 1st column
-//       3) denominator: conventional octal form of den. in 1st column
-//       4) N: the trellis length
 
-//   Outputs: weight_node, a struct that includes
-//       1) weight_spectrum: a (d_max+1)-by-1 column vector denoting the #
-//           codewords of weight i. Index 'i' represents weight 'i-1'.
-//       2) overall_weight_function: a polynomial representation of WEF
-
-//   Written by Hengjie Yang (hengjie.yang@ucla.edu) 03/30/21
-//   Translated into Rust by Holden Grissett (holdengs@g.ucla.edu) 08/15/2022
-
-pub fn compute_ztcc_weight_spectrum_fast(_v: u16, _numerators: [u16; 3], _denominator: u16, trellis_len: u16, trell: Trellis) -> Result<Poly<f64>, ParseIntError> {
+// Only entry (0, 0) of the N-th power of the transfer matrix is needed, so instead of multiplying
+// whole matrices this carries the row of state 0 through the trellis one section at a time:
+// row[s][d] is the number of paths from state 0 into state s with output weight d, and every state
+// only passes its row on to its 2^k successors. That is O(N S 2^k) polynomial additions instead of
+// O(N S^3) polynomial products. Weights above max_distance are dropped as they appear (pass
+// usize::MAX for the whole spectrum), which bounds the work and memory by max_distance rather than
+// n*N. Returns the coefficients of entry (0, 0) without trailing zeros.
+pub fn propagate_weight_enumerators<T>(trellis_len: u16, trell: &Trellis, max_distance: usize) -> Vec<T>
+where
+    T: Clone + num_traits::Zero + num_traits::One + for<'a> AddAssign<&'a T>,
+{
+    propagate_weight_enumerators_by_length(trellis_len, trell, max_distance).pop().unwrap_or_else(|| vec![T::one()])
+}
 
+// propagate_weight_enumerators for every trellis length 1..=N in the same pass: after section t
+// the row of state 0 is entry (0, 0) of the t-th power of the transfer matrix, i.e. the spectrum
+// of the length-t ZTCC. Element t - 1 of the result is that spectrum, without trailing zeros.
+pub fn propagate_weight_enumerators_by_length<T>(trellis_len: u16, trell: &Trellis, max_distance: usize) -> Vec<Vec<T>>
+where
+    T: Clone + num_traits::Zero + num_traits::One + for<'a> AddAssign<&'a T>,
+{
     let num_states = trell.num_states;
-    let mut transfer_function: DMatrix<PolyWrapper<f64>> = DMatrix::zeros(num_states, num_states);
-    let mut tmp;
-    let mut test_vec: Vec<Vec<i16>> = vec![vec![-1; num_states]; num_states];
-    println!("Step 1: Compute the transfer function");
-    for current_state in 0..trell.num_states {
-        for input in 0..trell.num_input_symbols {
-            let next_state = trell.next_states.index(current_state, input);
-            //let dec = u16::from_str_radix(&u16::from(*trell.outputs.index(current_state, input)).to_string(), 16);
-            let output = u16::from(*trell.outputs.index(current_state, input));
-            tmp = usize::from(*next_state);
-            transfer_function[(current_state, usize::from(*next_state))] = calc_polynomial_from_weight(output.count_ones()); // need to make polynomial conversion function
-            test_vec[current_state][tmp] = output.count_ones() as i16;
-            if current_state == 0 && tmp == 16 {
-                println!("{}", output);
-            }
-        }
-    }
+    let n = trell.num_output_symbols.trailing_zeros() as usize;
+    let max_weight = (n * usize::from(trellis_len)).min(max_distance);
 
-    println!("Step 2: compute the weight enumerating function for each starting state.");
-    print_matrix(test_vec);
-    let mut identity_matrix: DMatrix<PolyWrapper<f64>> = DMatrix::<PolyWrapper<f64>>::identity(num_states, num_states);
+    println!("Step 1: propagate the weight enumerating functions from state 0.");
+    let mut row: Vec<Vec<T>> = vec![vec![T::zero(); max_weight + 1]; num_states];
+    row[0][0] = T::one();
+    let mut weight_spectra: Vec<Vec<T>> = Vec::with_capacity(usize::from(trellis_len));
     for i in 0..trellis_len {
         println!("Current depths: {}", i);
-        identity_matrix = identity_matrix * &transfer_function;
-    }
+        let mut next_row: Vec<Vec<T>> = vec![vec![T::zero(); max_weight + 1]; num_states];
+        for (state, coefficients) in row.iter().enumerate() {
+            for input in 0..trell.num_input_symbols {
+                let next_state = *trell.next_states.index(state, input);
+                let weight = trell.outputs.index(state, input).count_ones() as usize;
+                if weight > max_weight {
+                    continue;
+                }
+                for (d, coefficient) in coefficients[..=(max_weight - weight)].iter().enumerate() {
+                    if !coefficient.is_zero() {
+                        next_row[next_state][d + weight] += coefficient;
+                    }
+                }
+            }
+        }
+        row = next_row;
 
-    // Step 3: Compute the overall weight enumerating function for finite-length ZTCC
-    println!("Step 3: Compute the overall weight enumerating function.");
+        let mut weight_spectrum = row[0].clone();
+        while weight_spectrum.len() > 1 && weight_spectrum.last().is_some_and(|c| c.is_zero()) {
+            weight_spectrum.pop();
+        }
+        weight_spectra.push(weight_spectrum);
+    }
 
-    let ret: Poly<f64> = identity_matrix[(0, 0)].clone().unwrap();
-    Ok(ret)
+    weight_spectra
 }
 
-fn print_matrix(matrix: Vec<Vec<i16>>) -> () {
-    for row in matrix {
-        for e in row {
-            if e == -1 {
-                print!("_ ");
-            } else {
-                print!("{} ", e);
-            }
+// Entry (0, 0) of the exponent-th power of the transfer matrix (of PolyWrapper or TruncatedPoly
+// entries) by repeated squaring: the row of state 0 is multiplied by transfer^(2^j) for every set
+// bit j of the exponent, so only O(S^3 log N) polynomial products are needed. This pays off over propagate_weight_enumerators
+// once N is much larger than S / 2^k times the (growing) cost of the longer polynomials.
+pub fn power_entry_by_squaring<E>(transfer_function: &DMatrix<E>, exponent: u64) -> E
+where
+    E: nalgebra::Scalar + num_traits::Zero + num_traits::One + nalgebra::ClosedAdd + nalgebra::ClosedMul,
+{
+    let num_states = transfer_function.nrows();
+    let mut row: DMatrix<E> = DMatrix::from_fn(1, num_states,
+        |_, state| if state == 0 { num_traits::One::one() } else { num_traits::Zero::zero() });
+    let mut square = transfer_function.clone();
+    let mut remaining = exponent;
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            row = &row * &square;
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            square = &square * &square;
         }
-        println!();
     }
+    row[(0, 0)].clone()
 }
 
-fn calc_polynomial_from_weight(weight: u32) -> PolyWrapper<f64> {
-    let polynomial: PolyWrapper<f64>;
-    if weight == 0 {
-        polynomial = poly!(1 as f64).wrap();
-    } else if weight == 1 {
-        polynomial = poly!(0 as f64, 1 as f64).wrap();
-    } else {
-        let mut tmp = vec![0 as f64; weight as usize];
-        tmp.push(1 as f64);
-        polynomial = Poly::new_from_coeffs_iter(tmp).wrap();
+// The whole exponent-th power of the transfer matrix by repeated squaring, for the tail-biting paths
+// which need every diagonal entry: O(S^3 log N) polynomial products instead of O(S^3 N)
+pub fn power_by_squaring<E>(transfer_function: &DMatrix<E>, exponent: u64) -> DMatrix<E>
+where
+    E: nalgebra::Scalar + num_traits::Zero + num_traits::One + nalgebra::ClosedAdd + nalgebra::ClosedMul,
+{
+    let num_states = transfer_function.nrows();
+    let mut power: DMatrix<E> = DMatrix::from_fn(num_states, num_states,
+        |row, col| if row == col { num_traits::One::one() } else { num_traits::Zero::zero() });
+    let mut square = transfer_function.clone();
+    let mut remaining = exponent;
+    while remaining > 0 {
+        if remaining & 1 == 1 {
+            power = &power * &square;
+        }
+        remaining >>= 1;
+        if remaining > 0 {
+            square = &square * &square;
+        }
     }
-
-    polynomial
+    power
 }
-
-#[cfg(test)]
-mod tests {
-
-    use polynomen::{poly, Zero, One};
-    use crate::{poly_wrapper::{PolyToWrapped}, compute_ztcc_weight_spectrum_fast::calc_polynomial_from_weight};
-
-    #[test]
-    fn test_calc_polynomial_basic() {
-        let polynomial = poly!(f64::zero(), f64::zero(), f64::one()).wrap();
-        assert_eq!(calc_polynomial_from_weight(2), polynomial);
-    }
-}
\ No newline at end of file
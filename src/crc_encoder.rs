@@ -0,0 +1,189 @@
+use bitvec::prelude::*;
+
+// Which end of the CRC polynomial the serial stream starts at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitOrder {
+    // the first bit is the highest power of x, as convert_ztps reads the ZTP bits
+    MsbFirst,
+    // the first bit is x^0 (a reflected CRC)
+    LsbFirst,
+}
+
+// How the serial stream of message and parity bits is laid out on the k input rails of the ZTCC,
+// whose input is read k bits per trellis transition with the first rail first
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RailMapping {
+    // consecutive stream bits fill the rails of one transition before moving to the next transition,
+    // so the stream is the trellis input itself (the layout of the IEEs and ZTPs)
+    Interleaved,
+    // the first N stream bits go on rail 1, the next N bits on rail 2, and so on
+    RailByRail,
+}
+
+// CRC with the generator found by dso_crc_polynomial_search (bit i holding the coefficient of x^i,
+// so the degree m is the position of the highest set bit). With MsbFirst and Interleaved, a ZTP
+// is undetected by check exactly when dso_crc_polynomial_search counts it as divisible by the CRC.
+#[derive(Debug, Clone)]
+pub struct CrcEncoder {
+    pub polynomial: u16,
+    pub bit_order: BitOrder,
+    pub rail_mapping: RailMapping,
+    pub k: usize,
+}
+
+impl CrcEncoder {
+    pub fn new(polynomial: u16, bit_order: BitOrder, rail_mapping: RailMapping, k: usize) -> CrcEncoder {
+        assert!(polynomial > 1 && polynomial & 1 == 1, "CRC polynomial {:#x} must have degree >= 1 and a x^0 term", polynomial);
+        assert!(k > 0, "k must be positive");
+        CrcEncoder { polynomial, bit_order, rail_mapping, k }
+    }
+
+    pub fn degree(&self) -> usize {
+        (u16::BITS - 1 - self.polynomial.leading_zeros()) as usize
+    }
+
+    // The m parity bits of a serial stream, in stream order
+    pub fn parity(&self, stream: &BitSlice) -> BitVec {
+        let m = self.degree();
+        let mut parity: BitVec = BitVec::with_capacity(m);
+        match self.bit_order {
+            BitOrder::MsbFirst => {
+                // shift register holding x^m * stream(x) mod g(x)
+                let mask: u16 = ((1_u32 << m) - 1) as u16;
+                let mut register: u16 = 0;
+                for bit in stream.iter() {
+                    let feedback = ((register >> (m - 1)) & 1) ^ u16::from(*bit);
+                    register = (register << 1) & mask;
+                    if feedback == 1 {
+                        register ^= self.polynomial & mask;
+                    }
+                }
+                for j in (0..m).rev() {
+                    parity.push((register >> j) & 1 == 1);
+                }
+            },
+            BitOrder::LsbFirst => {
+                // reflected register holding stream(x) * x^(-len) mod g(x)
+                let mut register: u16 = 0;
+                for bit in stream.iter() {
+                    register ^= u16::from(*bit);
+                    if register & 1 == 1 {
+                        register = (register >> 1) ^ (self.polynomial >> 1);
+                    } else {
+                        register >>= 1;
+                    }
+                }
+                for j in 0..m {
+                    parity.push((register >> j) & 1 == 1);
+                }
+            },
+        }
+        parity
+    }
+
+    // Appends the m parity bits to the message and lays the result out on the k rails, giving the
+    // trellis input for encode_ztcc. The message length plus m must be a multiple of k.
+    pub fn encode(&self, message: &BitSlice) -> BitVec {
+        let mut stream: BitVec = message.to_bitvec();
+        stream.extend_from_bitslice(&self.parity(message));
+        self.stream_to_rails(&stream)
+    }
+
+    // Checks a trellis input (without its termination) against the CRC
+    pub fn check(&self, trellis_input: &BitSlice) -> bool {
+        self.parity(&self.rails_to_stream(trellis_input)).not_any()
+    }
+
+    // Serial stream to trellis input (k bits per transition)
+    pub fn stream_to_rails(&self, stream: &BitSlice) -> BitVec {
+        assert!(stream.len() % self.k == 0, "stream length {} is not a multiple of k = {}", stream.len(), self.k);
+        match self.rail_mapping {
+            RailMapping::Interleaved => stream.to_bitvec(),
+            RailMapping::RailByRail => {
+                let trellis_len = stream.len() / self.k;
+                (0..stream.len()).map(|i| stream[(i % self.k) * trellis_len + i / self.k]).collect()
+            },
+        }
+    }
+
+    // Trellis input (k bits per transition) to serial stream
+    pub fn rails_to_stream(&self, trellis_input: &BitSlice) -> BitVec {
+        assert!(trellis_input.len() % self.k == 0, "input length {} is not a multiple of k = {}", trellis_input.len(), self.k);
+        match self.rail_mapping {
+            RailMapping::Interleaved => trellis_input.to_bitvec(),
+            RailMapping::RailByRail => {
+                let trellis_len = trellis_input.len() / self.k;
+                (0..trellis_input.len()).map(|i| trellis_input[(i % trellis_len) * self.k + i / trellis_len]).collect()
+            },
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::crc_encoder::{CrcEncoder, BitOrder, RailMapping};
+
+    use bitvec::prelude::*;
+    use galois_field::*;
+
+    // a fixed pseudo-random message
+    fn message(len: usize) -> BitVec {
+        let mut x: u32 = 0x2545;
+        (0..len).map(|_| { x ^= x << 13; x ^= x >> 17; x ^= x << 5; x & 1 == 1 }).collect()
+    }
+
+    #[test]
+    fn test_known_parity() {
+        // 1101 with g(x) = x^3 + x + 1: x^3 (x^3 + x^2 + 1) mod g(x) = 1, so the parity is 001
+        let crc = CrcEncoder::new(0b1011, BitOrder::MsbFirst, RailMapping::Interleaved, 1);
+        assert_eq!(crc.parity(bits![1, 1, 0, 1]), bitvec![0, 0, 1]);
+        assert_eq!(crc.encode(bits![1, 1, 0, 1]), bitvec![1, 1, 0, 1, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_encode_then_check() {
+        for bit_order in [BitOrder::MsbFirst, BitOrder::LsbFirst] {
+            for rail_mapping in [RailMapping::Interleaved, RailMapping::RailByRail] {
+                let crc = CrcEncoder::new(0x709, bit_order, rail_mapping, 3);
+                let message = message(3 * 20 - crc.degree());
+                let mut input = crc.encode(&message);
+                assert!(crc.check(&input));
+                assert_eq!(crc.rails_to_stream(&input)[..message.len()], message);
+                let flipped = !input[7];
+                input.set(7, flipped);
+                assert!(!crc.check(&input));
+            }
+        }
+    }
+
+    #[test]
+    fn test_rail_by_rail_layout() {
+        // stream a0 a1 b0 b1 on k = 2 rails: transition 0 carries (a0, b0) and transition 1 (a1, b1)
+        let crc = CrcEncoder::new(0b11, BitOrder::MsbFirst, RailMapping::RailByRail, 2);
+        assert_eq!(crc.stream_to_rails(bits![1, 0, 0, 0]), bitvec![1, 0, 0, 0]);
+        assert_eq!(crc.stream_to_rails(bits![0, 1, 0, 0]), bitvec![0, 0, 1, 0]);
+        assert_eq!(crc.stream_to_rails(bits![0, 0, 1, 0]), bitvec![0, 1, 0, 0]);
+        assert_eq!(crc.rails_to_stream(&crc.stream_to_rails(bits![1, 1, 0, 1])), bitvec![1, 1, 0, 1]);
+    }
+
+    #[test]
+    fn test_consistent_with_convert_ztps() {
+        // convert_ztps reads the ZTP input bits as a polynomial with the first bit as the highest power
+        let e0 = FiniteField { char: 2, element: Element::PrimeField { element: 0 } };
+        let e1 = FiniteField { char: 2, element: Element::PrimeField { element: 1 } };
+        let to_poly = |bits: &BitSlice| Polynomial { coef: bits.iter().map(|b| if *b { e1.clone() } else { e0.clone() }).collect() };
+
+        let crc = CrcEncoder::new(0x709, BitOrder::MsbFirst, RailMapping::Interleaved, 3);
+        let crc_poly = to_poly(&(0..=crc.degree()).rev().map(|j| (crc.polynomial >> j) & 1 == 1).collect::<BitVec>());
+        for shift in 0..10 {
+            let error = message(30 + 3 * shift);
+            let remainder = to_poly(&error) % crc_poly.clone();
+            assert_eq!(crc.check(&error), !remainder.coef.iter().any(|x| x.is_1()));
+        }
+        let codeword = crc.encode(&message(42 - crc.degree()));
+        let remainder = to_poly(&codeword) % crc_poly;
+        assert!(!remainder.coef.iter().any(|x| x.is_1()));
+    }
+}
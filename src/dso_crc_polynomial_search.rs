@@ -21,17 +21,22 @@ pub struct ZTP_Polys {
     pub aggregate: usize
 }
 
-pub fn dso_crc_polynomial_search(v: u16, numerators: [u16; 3], octal_denominator: u16,
+// Searches for the distance-spectrum optimal CRC of degree polynomial_degree for a ZTCC with
+// k input rails and mu termination transitions, given the ZTPs from reconstruct_ztps.
+// For a TBCC, pass the TBPs from find_tail_biting_paths and mu = 0, since a tail-biting
+// code spends no input bits on termination.
+// Returns None if the candidates are still tied at max_search_distance, and the DSO CRC otherwise,
+// even if it leaves no ZTP up to max_search_distance undetected.
+pub fn dso_crc_polynomial_search(k: usize, mu: usize,
     max_search_distance: usize, trellis_len: u16, polynomial_degree: u32,
-    classic_ztps: ZTPs) -> u16 {
+    classic_ztps: ZTPs) -> Option<u16> {
 
-    let mut crc_polynomial: Poly<u16> = Poly { poly: Polynomial { coef: vec![] }, poly_data: 0, data_idx: 0 };
+    let mut crc_polynomial: Option<u16> = None;
 
     let stopped_distance = -1;
     let mut success = false;
-    let mut min_distance: i32 = -1;
-    let k: u16 = numerators.len() as u16; // # input rails
-    let mu: u32 = ((v as f32 - 1.) / k as f32).ceil() as u32; // the # termination transitions
+    let k: u16 = k as u16; // # input rails
+    let mu: u32 = mu as u32; // the # termination transitions
 
     
     println!("Step 0: convert CRCs into polynomial data structure");
@@ -105,30 +110,42 @@ pub fn dso_crc_polynomial_search(v: u16, numerators: [u16; 3], octal_denominator
 
     if success {
         println!("Step 4: Identify the minimum undetected distance by the DSO CRC");
-        for distance in 1..max_search_distance {
+        crc_polynomial = Some(crc_gen_polynomials[0].poly_data);
+        println!("DSO CRC Polynomial: {:#x}", crc_gen_polynomials[0].poly_data);
+        for distance in 1..(max_search_distance + 1) {
             if !ztps.zero_terminated_paths[distance].is_empty() {
                 let w = check_divisible_by_distance(crc_gen_polynomials[0].clone(), ztps.zero_terminated_paths[distance].clone(), k, mu);
                 if w > 0 {
-                    min_distance = distance as i32;
-                    crc_polynomial = crc_gen_polynomials[0].clone();
-                    println!("DSO CRC Polynomial: {:#x}", crc_gen_polynomials[0].poly_data);
-                    println!("Minimum undetected distance: {}", min_distance);
+                    println!("Minimum undetected distance: {}", distance);
                     break;
                 }
-                if w == 0 && distance == max_search_distance {
-                    println!("max_search_distance is insufficient to determine the minimum undetected distance");
-                }
+            }
+            if distance == max_search_distance {
+                println!("max_search_distance is insufficient to determine the minimum undetected distance");
             }
         }
     }
 
-    println!("{:#x}", crc_polynomial.poly_data);
-
-    crc_polynomial.poly_data
+    crc_polynomial
 }
 
 
 fn generate_crcs(list_size: u16, polynomial_degree: u32) -> Vec<Poly<u16>> {
+    let crc_polys = (0..list_size).map(|crc_idx: u16| {
+        let mut crc: u16;
+        crc = crc_idx | (1 << (polynomial_degree - 1));
+        crc = crc << 1;
+        crc = crc | 1;
+        let mut crc_poly = crc_to_poly(crc, polynomial_degree);
+        crc_poly.data_idx = crc_idx;
+        crc_poly
+    }).collect();
+
+    crc_polys
+}
+
+// Turns a CRC generator (bit i holding the coefficient of x^i) into the polynomial data structure
+fn crc_to_poly(crc: u16, polynomial_degree: u32) -> Poly<u16> {
     let char: u32 = 2;
     let e0: FiniteField = FiniteField{
         char: char,
@@ -139,24 +156,36 @@ fn generate_crcs(list_size: u16, polynomial_degree: u32) -> Vec<Poly<u16>> {
         element: Element::PrimeField {element: 1} // 1
     };
 
-    let crc_polys = (0..list_size).map(|crc_idx: u16| {
-        let mut crc: u16;
-        crc = crc_idx | (1 << (polynomial_degree - 1));
-        crc = crc << 1;
-        crc = crc | 1;
-        let crc_vec: Vec<FiniteField> = (0..(polynomial_degree + 1)).rev().map(|x| { 
-            if (1 & (crc >> x)) == 0 { return e0.clone(); }
-            else { return e1.clone() };
-        }).collect();
-        let crc_poly: Polynomial = Polynomial { coef: crc_vec };
-        Poly {
-            poly: crc_poly,
-            poly_data: crc,
-            data_idx: crc_idx
-        }
+    let crc_vec: Vec<FiniteField> = (0..(polynomial_degree + 1)).rev().map(|x| { 
+        if (1 & (crc >> x)) == 0 { return e0.clone(); }
+        else { return e1.clone() };
     }).collect();
+    Poly {
+        poly: Polynomial { coef: crc_vec },
+        poly_data: crc,
+        data_idx: 0
+    }
+}
 
-    crc_polys
+// Counts, for every distance up to max_search_distance, the ZTPs that the CRC fails to detect
+// (those divisible by it). Index i holds distance i, and index 0 is always 0.
+pub fn compute_undetected_spectrum(k: usize, mu: usize,
+    max_search_distance: usize, crc: u16, polynomial_degree: u32,
+    classic_ztps: ZTPs) -> Vec<u32> {
+
+    let k: u16 = k as u16;
+    let mu: u32 = mu as u32;
+    let crc_poly = crc_to_poly(crc, polynomial_degree);
+    let ztps = convert_ztps(classic_ztps, k, mu);
+
+    let mut undetected_spectrum: Vec<u32> = vec![0; max_search_distance + 1];
+    for (count, paths) in undetected_spectrum.iter_mut().zip(&ztps.zero_terminated_paths).skip(1) {
+        if !paths.is_empty() {
+            *count = check_divisible_by_distance(crc_poly.clone(), paths.clone(), k, mu);
+        }
+    }
+
+    undetected_spectrum
 }
 
 fn convert_ztps(ztps: ZTPs, k: u16, mu: u32) -> ZTP_Polys {
@@ -250,4 +279,29 @@ fn check_divisible_by_distance(crc: Poly<u16>, error_events: Vec<Poly<BitVec>>,
     //println!("{weight}");
 
     weight
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::dso_crc_polynomial_search::dso_crc_polynomial_search;
+    use crate::reconstruct_ztps::ZTPs;
+
+    use bitvec::prelude::*;
+
+    #[test]
+    fn test_crc_without_undetected_ztps_wins() {
+        // the only ZTP is x^2 + 1, which x^2 + 1 (0x5) divides and x^2 + x + 1 (0x7) does not,
+        // so 0x7 is the DSO CRC even though it leaves nothing undetected
+        let ztps = ZTPs { zero_terminated_paths: vec![vec![], vec![bitvec![1, 0, 1]]], aggregate: 1 };
+        assert_eq!(dso_crc_polynomial_search(1, 0, 1, 3, 2, ztps), Some(0x7));
+    }
+
+    #[test]
+    fn test_tie_at_max_search_distance() {
+        // x + 1 is divisible by neither candidate
+        let ztps = ZTPs { zero_terminated_paths: vec![vec![], vec![bitvec![1, 1]]], aggregate: 1 };
+        assert_eq!(dso_crc_polynomial_search(1, 0, 1, 2, 2, ztps), None);
+    }
+}
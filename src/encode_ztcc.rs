@@ -0,0 +1,70 @@
+use crate::trellis::trellis::Trellis;
+
+use bitvec::prelude::*;
+
+// Encodes a k*N-bit message with the ZTCC described by `trellis`, starting from state 0.
+// The message is read k bits per trellis transition with the first rail as MSB, the same layout
+// the IEEs and ZTPs use. After the N message transitions the termination inputs of the final state
+// are appended, so the codeword ends in state 0 and has n*(N + mu) bits, n bits per transition with
+// the first output as MSB.
+pub fn encode_ztcc(message: &BitSlice, trellis: &Trellis) -> BitVec {
+
+    let k = trellis.num_input_rails;
+    let n = trellis.num_output_symbols.trailing_zeros() as usize;
+    assert!(message.len() % k == 0, "message length {} is not a multiple of k = {}", message.len(), k);
+
+    let mut codeword: BitVec = BitVec::with_capacity(n * (message.len() / k + trellis.termination_length));
+    let mut state: usize = 0;
+
+    for section in message.chunks(k) {
+        let input = section.iter().fold(0_usize, |symbol, bit| (symbol << 1) | usize::from(*bit));
+        push_output(&mut codeword, *trellis.outputs.index(state, input), n);
+        state = *trellis.next_states.index(state, input);
+    }
+
+    for &input in &trellis.terminations[state] {
+        push_output(&mut codeword, *trellis.outputs.index(state, input), n);
+        state = *trellis.next_states.index(state, input);
+    }
+    debug_assert_eq!(state, 0, "termination did not end in state 0");
+
+    codeword
+}
+
+fn push_output(codeword: &mut BitVec, output: u64, n: usize) {
+    for j in (0..n).rev() {
+        codeword.push((output >> j) & 1 == 1);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+
+    #[test]
+    fn test_encode_rate_1_2() {
+        // poly2trellis(3, [7 5]) encodes 1011 (+ two flush zeros) as 11 10 00 01 01 11
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let codeword = encode_ztcc(bits![1, 0, 1, 1], &trellis);
+        assert_eq!(codeword, bitvec![1, 1, 1, 0, 0, 0, 0, 1, 0, 1, 1, 1]);
+    }
+
+    #[test]
+    fn test_encode_systematic_feedback() {
+        // the feedback encoder puts the parity bit first, followed by the k message bits
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        let message = bitvec![1, 0, 0, 1, 1, 1, 0, 1, 0, 0, 0, 1];
+        let codeword = encode_ztcc(&message, &trellis);
+        assert_eq!(codeword.len(), 4 * (4 + trellis.termination_length));
+        for (section, message_section) in codeword.chunks(4).zip(message.chunks(3)) {
+            assert_eq!(&section[1..], message_section);
+        }
+    }
+}
@@ -14,12 +14,14 @@ pub struct ErrorEvents {
     pub error_event_lengths: Vec<Vec<u32>>
 }
 
-pub fn find_irreducible_error_event(
-    _v: u16, numerators: [u16; 3], _denominator: u16, max_search_distance: usize, trellis: &Trellis
-) -> ErrorEvents {
+// Finds the irreducible error events (IEEs) of the code described by `trellis` whose output
+// weight is at most max_search_distance, error_events[w - 1] holding those of weight w. The number
+// of input rails k is taken from the trellis, and each IEE is stored as its input bits, k bits per
+// trellis transition (MSB rail first).
+pub fn find_irreducible_error_event(max_search_distance: usize, trellis: &Trellis) -> ErrorEvents {
     
     let _path: String = "./Simulation_results/".to_owned();
-    let k = numerators.len();
+    let k = trellis.num_input_rails;
 
     // let mut num_string: String = "".to_owned();
 
@@ -53,36 +55,40 @@ pub fn find_irreducible_error_event(
 
         if i == 0 {
             for input in 1..trellis.num_input_symbols {
-                let next_state = u16::from(*trellis.next_states.index(0, input)) as usize;
+                let next_state = *trellis.next_states.index(0, input);
+                // punctured trellises can leave state 0 on a zero-weight branch
+                let weight = trellis.outputs.index(0, input).count_ones() as usize;
+                if weight > max_search_distance {
+                    continue;
+                }
                 if column[index][next_state].is_empty() {
-                    column[index][next_state] = vec![vec![]; max_search_distance];
+                    column[index][next_state] = vec![vec![]; max_search_distance + 1];
                 }
-                let weight = trellis.outputs.index(0, input).count_ones();
 
                 let mut input_bits: BitVec = bitvec![];
                 for n in (0..k).rev() {
                     let bit = input >> n & 1;
                     input_bits.push(bit != 0); // I'm just converting the nth input bit to a bool before pushing it to the BitVec
                 }
-                column[index][next_state as usize][(weight - 1) as usize].push(input_bits);
+                column[index][next_state][weight].push(input_bits);
             }
         } else {
             for current_state in 1..trellis.num_states {
                 if !column[prev_index][current_state].is_empty() {
-                    for distance in 0..max_search_distance {
+                    for distance in 0..(max_search_distance + 1) {
                         if !column[prev_index][current_state][distance].is_empty() {
                             for input in 0..trellis.num_input_symbols {
 
-                                let next_state = usize::from(*trellis.next_states.index(current_state, input)); // safe
+                                let next_state = *trellis.next_states.index(current_state, input); // safe
                                 if column[index][next_state].is_empty() {
-                                    column[index][next_state] = vec![vec![]; max_search_distance];
+                                    column[index][next_state] = vec![vec![]; max_search_distance + 1];
                                 }
 
                                 let weight = trellis.outputs.index(current_state, input).count_ones() as usize; // safe
 
                                 // Append input bits to each element in tmp
                                 
-                                if distance + weight < max_search_distance {
+                                if distance + weight <= max_search_distance {
                                     let mut tmp = column[prev_index][current_state][distance].clone();
                                     for m in 0..tmp.len() {
                                         for n in (0..k).rev() {
@@ -108,26 +114,28 @@ pub fn find_irreducible_error_event(
     let mut error_events: Vec<Vec<BitVec>> = vec![vec![]; max_search_distance];
     let mut error_event_lengths: Vec<Vec<u32>> = vec![vec![]; max_search_distance];
     
-    for i in 0..MAX_ITERATION {
-        if !zero_state[i].is_empty() {
-            for distance in 0..max_search_distance {
-                if !zero_state[i][distance].is_empty() {
-                    let mut new_len = 0;
+    for (i, paths_by_weight) in zero_state.iter().enumerate().take(MAX_ITERATION) {
+        if !paths_by_weight.is_empty() {
+            // zero_state is indexed by weight and error_events by weight - 1; a weight-0 return to
+            // state 0 is not an error event of any distance, so it is left out
+            for (weight, paths) in paths_by_weight.iter().enumerate().take(max_search_distance + 1).skip(1) {
+                let distance = weight - 1;
+                if !paths.is_empty() {
                     if error_events[distance].is_empty() {
-                        error_events[distance] = zero_state[i][distance].clone();
+                        error_events[distance] = paths.clone();
                     } else {
                         // pad with zeros until uniform length
 
-                        for n in 0..error_events[distance].len() {
-                            let length = error_events[distance][n].len();
-                            error_events[distance][n].extend(bitvec![0; (k * (i + 1) - length)]);
+                        for event in error_events[distance].iter_mut() {
+                            let length = event.len();
+                            event.extend(bitvec![0; (k * (i + 1) - length)]);
                         }
-                        error_events[distance].append(&mut zero_state[i][distance].clone());
+                        error_events[distance].append(&mut paths.clone());
                     }
-                    let new_len = zero_state[i][distance].len();
+                    let new_len = paths.len();
                     println!("new_len {}", new_len);
-                    for n in 0..new_len {
-                        error_event_lengths[distance].push(zero_state[i][distance][n].len() as u32);
+                    for path in paths {
+                        error_event_lengths[distance].push(path.len() as u32);
                     }
                 }
             }
@@ -139,3 +147,48 @@ pub fn find_irreducible_error_event(
         error_event_lengths
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::find_irreducible_error_event::find_irreducible_error_event;
+    use crate::trellis::puncturing::PuncturingPattern;
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_punctured_trellis() {
+        // the last step of every period is punctured, so input 001 leaves state 0 on a zero-weight branch
+        let mother = generate_rate_1_n_trellis(3, &[7, 5], None).unwrap();
+        let pattern = PuncturingPattern::new(Vec2d::new(vec![true, true, false, true, true, false], 2, 3));
+        let punctured = pattern.puncture(&mother).unwrap();
+        let zero_weight_input = 0b001;
+        assert_ne!(*punctured.next_states.index(0, zero_weight_input), 0);
+        assert_eq!(*punctured.outputs.index(0, zero_weight_input), 0);
+
+        let max_search_distance = 4;
+        let error_events = find_irreducible_error_event(max_search_distance, &punctured);
+        let k = punctured.num_input_rails;
+        let mut starts_with_zero_weight = false;
+        for (distance, events) in error_events.error_events.iter().enumerate() {
+            for (event, &length) in events.iter().zip(&error_events.error_event_lengths[distance]) {
+                // every IEE leaves state 0, returns to it only at its end, and has weight distance + 1
+                let mut state = 0;
+                let mut weight = 0;
+                for (section, bits) in event[..length as usize].chunks(k).enumerate() {
+                    let input = bits.iter().fold(0, |input, bit| (input << 1) | usize::from(*bit));
+                    if section == 0 {
+                        starts_with_zero_weight |= input == zero_weight_input;
+                    } else {
+                        assert_ne!(state, 0);
+                    }
+                    weight += punctured.outputs.index(state, input).count_ones() as usize;
+                    state = *punctured.next_states.index(state, input);
+                }
+                assert_eq!((state, weight), (0, distance + 1));
+            }
+        }
+        assert!(starts_with_zero_weight);
+    }
+}
@@ -0,0 +1,128 @@
+use rayon::prelude::*;
+
+use crate::trellis::trellis::Trellis;
+use crate::reconstruct_ztps::ZTPs;
+
+use bitvec::prelude::*;
+
+//  This function enumerates all tail-biting paths (TBPs) of a length-N
+//  tail-biting convolutional code (TBCC) with distance at most d_tilde.
+//  A TBP is a length-N path that starts and ends in the same (arbitrary)
+//  state, so unlike ZTPs they cannot be rebuilt from the IEEs leaving the
+//  zero state and are searched for directly from every starting state.
+
+//  Input parameters:
+//    1) d_tilde: a scalar denoting the distance threshold
+//    2) N: a scalar denoting the primal trellis length
+//    3) trellis: the trellis of the TBCC
+
+//  Output parameters: the TBPs in the same layout as the ZTPs, so that they
+//    can be handed to dso_crc_polynomial_search with mu = 0:
+//    1) list: a (d_tilde+1)-by-1 column vector denoting the list of length-kN
+//        input sequences of the TBPs arranged in ascending distances
+//    2) aggregate: a scalar denoting the number of TBPs of distance at most d_tilde
+pub fn find_tail_biting_paths(max_search_distance: usize, trellis_len: u16, trellis: &Trellis) -> ZTPs {
+
+    let k = trellis.num_input_rails;
+    let num_states = trellis.num_states;
+    let trellis_len = trellis_len as usize;
+
+    let paths_by_start_state: Vec<Vec<Vec<BitVec>>> = (0..num_states).into_par_iter().map(|start_state| {
+
+        // min_weight_to_go[r][state]: the smallest weight of a length-r path from state back to start_state,
+        // used to prune partial paths that cannot close within the distance threshold
+        let unreachable = usize::MAX;
+        let mut min_weight_to_go: Vec<Vec<usize>> = vec![vec![unreachable; num_states]; trellis_len + 1];
+        min_weight_to_go[0][start_state] = 0;
+        for remaining in 1..(trellis_len + 1) {
+            for state in 0..num_states {
+                for input in 0..trellis.num_input_symbols {
+                    let next_state = *trellis.next_states.index(state, input);
+                    let rest = min_weight_to_go[remaining - 1][next_state];
+                    if rest != unreachable {
+                        let weight = trellis.outputs.index(state, input).count_ones() as usize + rest;
+                        if weight < min_weight_to_go[remaining][state] {
+                            min_weight_to_go[remaining][state] = weight;
+                        }
+                    }
+                }
+            }
+        }
+
+        // column[state][distance]: the partial paths from start_state ending in state
+        let mut column: Vec<Vec<Vec<BitVec>>> = vec![vec![vec![]; max_search_distance + 1]; num_states];
+        if min_weight_to_go[trellis_len][start_state] <= max_search_distance {
+            column[start_state][0].push(bitvec![]);
+        }
+
+        for depth in 0..trellis_len {
+            let remaining = trellis_len - depth - 1;
+            let mut next_column: Vec<Vec<Vec<BitVec>>> = vec![vec![vec![]; max_search_distance + 1]; num_states];
+            for (state, paths_at_state) in column.iter().enumerate() {
+                for (distance, paths) in paths_at_state.iter().enumerate() {
+                    if paths.is_empty() {
+                        continue;
+                    }
+                    for input in 0..trellis.num_input_symbols {
+                        let next_state = *trellis.next_states.index(state, input);
+                        let weight = trellis.outputs.index(state, input).count_ones() as usize;
+                        let rest = min_weight_to_go[remaining][next_state];
+                        if rest == unreachable || distance + weight + rest > max_search_distance {
+                            continue;
+                        }
+                        for path in paths {
+                            let mut new_path = path.clone();
+                            for n in (0..k).rev() {
+                                new_path.push((input >> n) & 1 != 0);
+                            }
+                            next_column[next_state][distance + weight].push(new_path);
+                        }
+                    }
+                }
+            }
+            column = next_column;
+        }
+
+        column.swap_remove(start_state)
+    }).collect();
+
+    let mut tail_biting_paths: Vec<Vec<BitVec>> = vec![vec![]; max_search_distance + 1];
+    for paths in paths_by_start_state {
+        for (distance, mut paths_at_distance) in paths.into_iter().enumerate() {
+            tail_biting_paths[distance].append(&mut paths_at_distance);
+        }
+    }
+
+    let aggregate = tail_biting_paths.iter().map(|paths| paths.len()).sum();
+    for (distance, paths) in tail_biting_paths.iter().enumerate() {
+        println!("# TBPs of distance {}: {}", distance, paths.len());
+    }
+
+    ZTPs {
+        zero_terminated_paths: tail_biting_paths,
+        aggregate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::compute_tbcc_weight_spectrum::compute_tbcc_weight_spectrum;
+    use crate::find_tail_biting_paths::find_tail_biting_paths;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_counts_match_weight_spectrum() {
+        let trellis = generate_feedback_trellis(4, &[15, 17], 13).unwrap();
+        let tail_biting_paths = find_tail_biting_paths(4, 6, &trellis);
+        let weight_spectrum = compute_tbcc_weight_spectrum(6, trellis).unwrap();
+        for (paths, count) in tail_biting_paths.zero_terminated_paths.iter().zip(&weight_spectrum[..5]) {
+            assert_eq!(BigUint::from(paths.len()), *count);
+            for path in paths {
+                assert_eq!(path.len(), 12);
+            }
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+use crate::find_irreducible_error_event::find_irreducible_error_event;
+use crate::compute_ztcc_weight_spectrum::compute_ztcc_weight_spectrum;
+use crate::reconstruct_ztps::reconstruct_ztps;
+use crate::dso_crc_polynomial_search::{dso_crc_polynomial_search, compute_undetected_spectrum};
+
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+
+// A feedback encoder together with its DSO CRC and the number of undetected ZTPs at each distance
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrcZtccPair {
+    pub numerators: Vec<u64>,
+    pub denominator: u64,
+    pub crc: u16,
+    pub undetected_spectrum: Vec<u32>,
+}
+
+//   This function jointly designs the ZTCC and the CRC: for every candidate feedback encoder with
+//   v taps it runs the IEE, weight spectrum, ZTP and DSO CRC stages, and ranks the resulting
+//   CRC-ZTCC pairs by their undetected distance spectrum. Only rate-k/(k+1) feedback encoders of
+//   generate_feedback_trellis are covered, with their shortest terminations and without puncturing.
+
+//   Inputs:
+//       1) v: the number of taps of every encoder polynomial
+//       2) encoders: the (numerators, denominator) candidates in octal, e.g. a published table
+//          or the output of ztcc_code_search
+//       3) max_search_distance: the distance up to which ZTPs are enumerated
+//       4) trellis_len: the trellis length N
+//       5) polynomial_degree: the CRC degree m
+
+//   Outputs: one pair per valid encoder for which the DSO CRC could be identified, best first (the
+//   largest minimum undetected distance, then the fewest undetected ZTPs at that distance, and so on)
+pub fn joint_crc_ztcc_search(v: u16, encoders: &[(Vec<u64>, u64)],
+    max_search_distance: usize, trellis_len: u16, polynomial_degree: u32) -> Vec<CrcZtccPair> {
+
+    let mut pairs: Vec<CrcZtccPair> = vec![];
+
+    for (numerators, denominator) in encoders {
+        println!("Encoder: numerators {:?}, denominator {}", numerators, denominator);
+        let trellis = match generate_feedback_trellis(v, numerators, *denominator) {
+            Ok(trellis) => trellis,
+            Err(error) => {
+                println!("Invalid encoder ({}), skipping this encoder", error);
+                continue;
+            },
+        };
+        let k = trellis.num_input_rails;
+        let mu = trellis.termination_length;
+
+        let error_events = find_irreducible_error_event(max_search_distance, &trellis);
+        let weight_spectrum = compute_ztcc_weight_spectrum(trellis_len, trellis).unwrap();
+        let ztps = reconstruct_ztps(k, max_search_distance, trellis_len, weight_spectrum, error_events);
+
+        let crc = match dso_crc_polynomial_search(k, mu, max_search_distance, trellis_len, polynomial_degree, ztps.clone()) {
+            Some(crc) => crc,
+            None => {
+                println!("No unique DSO CRC within max_search_distance, skipping this encoder");
+                continue;
+            },
+        };
+        let undetected_spectrum = compute_undetected_spectrum(k, mu, max_search_distance, crc, polynomial_degree, ztps);
+
+        pairs.push(CrcZtccPair {
+            numerators: numerators.clone(),
+            denominator: *denominator,
+            crc,
+            undetected_spectrum,
+        });
+    }
+
+    pairs.sort_by(|a, b| compare_undetected_spectra(&a.undetected_spectrum, &b.undetected_spectrum));
+
+    pairs
+}
+
+// Orders undetected spectra so that the one with fewer undetected ZTPs at the lowest differing distance comes first
+fn compare_undetected_spectra(a: &[u32], b: &[u32]) -> Ordering {
+    for (count_a, count_b) in a.iter().zip(b) {
+        match count_a.cmp(count_b) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::joint_crc_ztcc_search::{joint_crc_ztcc_search, compare_undetected_spectra};
+
+    use std::cmp::Ordering;
+
+    #[test]
+    fn test_undetected_spectrum_order() {
+        // a larger minimum undetected distance wins, then fewer undetected ZTPs at that distance
+        assert_eq!(compare_undetected_spectra(&[0, 0, 0, 5], &[0, 0, 1, 0]), Ordering::Less);
+        assert_eq!(compare_undetected_spectra(&[0, 0, 2, 9], &[0, 0, 3, 0]), Ordering::Less);
+        assert_eq!(compare_undetected_spectra(&[0, 0, 3, 0], &[0, 0, 3, 0]), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_invalid_encoders_are_skipped() {
+        // a denominator without a D^0 term and a numerator wider than v taps
+        let encoders = vec![(vec![15], 12), (vec![75], 13)];
+        assert!(joint_crc_ztcc_search(4, &encoders, 6, 8, 3).is_empty());
+    }
+}
@@ -2,39 +2,104 @@ mod find_dominant_error_event_fast;
 mod trellis;
 mod vec2d;
 mod find_irreducible_error_event;
+mod compute_ztcc_weight_spectrum;
 mod compute_ztcc_weight_spectrum_fast;
+mod compute_tbcc_weight_spectrum;
+mod find_tail_biting_paths;
+mod ztcc_code_search;
+mod joint_crc_ztcc_search;
+mod encode_ztcc;
+mod crc_encoder;
+mod viterbi_decoder;
+mod serial_list_viterbi_decoder;
+mod parallel_list_viterbi_decoder;
+mod random;
+mod simulate_fer;
+mod union_bounds;
 mod reconstruct_ztps;
 mod dso_crc_polynomial_search;
 pub mod poly_wrapper;
+pub mod truncated_poly;
 
 use std::time::Instant;
 
 use find_irreducible_error_event::{find_irreducible_error_event, ErrorEvents};
 
 use trellis::generate_feedback_trellis::generate_feedback_trellis;
+use trellis::poly2trellis::{poly2trellis, poly2trellis_with_feedback};
+use trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+use trellis::rational_generator_matrix::RationalGeneratorMatrix;
+use trellis::puncturing::PuncturingPattern;
+use trellis::distance::{find_free_distance, column_distance_profile};
+use trellis::transfer_function::{find_transfer_function, find_input_output_transfer_function};
+use trellis::catastrophic::{find_zero_output_cycle, is_catastrophic, is_catastrophic_rate_1_n};
 
-use compute_ztcc_weight_spectrum_fast::compute_ztcc_weight_spectrum_fast;
+use compute_ztcc_weight_spectrum::{compute_ztcc_weight_spectrum, compute_ztcc_weight_spectrum_by_squaring,
+    compute_ztcc_weight_spectrum_truncated, compute_ztcc_weight_spectrum_truncated_by_squaring,
+    compute_ztcc_iowe, iowe_to_json, iowe_from_json, iowe_matches_weight_spectrum, compute_ztcc_weight_spectrum_table, weight_spectrum_to_json, weight_spectrum_from_json, weight_spectrum_to_f64};
+use compute_tbcc_weight_spectrum::compute_tbcc_weight_spectrum;
+use find_tail_biting_paths::find_tail_biting_paths;
+use ztcc_code_search::ztcc_code_search;
+use joint_crc_ztcc_search::joint_crc_ztcc_search;
+use crc_encoder::{CrcEncoder, BitOrder, RailMapping};
+use simulate_fer::{simulate_fer, ListDecoder, SimulationConfig};
+use union_bounds::{compute_input_weighted_spectrum, input_weighted_spectrum_from_iowe, frame_error_union_bound, bit_error_union_bound, undetected_error_union_bound, Channel, UnionBounds};
 
 use reconstruct_ztps::{reconstruct_ztps, ZTPs};
 
-use dso_crc_polynomial_search::dso_crc_polynomial_search;
+use dso_crc_polynomial_search::{dso_crc_polynomial_search, compute_undetected_spectrum};
 
 use std::fs;
 use bincode;
-use polynomen::Poly;
 use num_bigint::BigUint;
 use std::env;
 use std::path::Path;
 use bitvec::prelude::*;
+use vec2d::Vec2d;
 
 
 fn main() {
     const V: u16 = 7;
-    const NUMERATOR: [u16; 3] = [107, 135, 133];
-    const DENOMINATOR: u16 = 141;
+    const NUMERATOR: &[u64] = &[107, 135, 133];
+    const DENOMINATOR: u64 = 141;
+    // MATLAB-style poly2trellis code, used instead of the feedback code when run with "poly2trellis"
+    // (an empty FEEDBACK_CONNECTIONS gives a feedforward encoder)
+    const CONSTRAINT_LENGTHS: &[u16] = &[5, 4];
+    const CODE_GENERATOR: [u64; 6] = [23, 35, 0, 0, 5, 13];
+    const FEEDBACK_CONNECTIONS: &[u64] = &[];
+    // rate-1/n code, used when run with "rate_1_n" (feedback of None gives a feedforward encoder)
+    const RATE_1_N_CONSTRAINT_LENGTH: u16 = 4;
+    const RATE_1_N_GENERATORS: &[u64] = &[13, 17];
+    const RATE_1_N_FEEDBACK: Option<u64> = Some(13);
+    // k-by-n rational generator matrix in MATLAB octal, used when run with "rational"
+    const RATIONAL_CONSTRAINT_LENGTHS: &[u16] = &[4, 4];
+    const RATIONAL_NUMERATORS: [u64; 6] = [10, 0, 15, 0, 10, 17];
+    const RATIONAL_DENOMINATORS: [u64; 6] = [10, 10, 13, 10, 10, 13];
+    // n-by-P puncturing pattern applied to the selected code when run with "punctured"
+    // (the trellis length then counts periods of P trellis sections)
+    const PUNCTURING_PATTERN: [bool; 6] = [true, true, false, true, false, true];
+    const PUNCTURING_PERIOD: usize = 3;
     const MAX_SEARCH_DISTANCE: usize = 10;
     const TRELLIS_LEN: u16 = 54;
     const TARGET_CRC_DEGREE: u32 = 10;
+    // number of trellis sections in the printed column distance profile
+    const DISTANCE_PROFILE_COLUMNS: usize = 12;
+    // degree up to which the series of T(D) and T(I, D) are printed when run with "transfer_function"
+    const TRANSFER_FUNCTION_DEGREE: usize = 20;
+    // number of encoders that get a full weight spectrum when run with "code_search"
+    const CODE_SEARCH_CANDIDATES: usize = 20;
+    // Monte Carlo FER simulation of the code with its DSO CRC when run with "simulate"
+    const EB_N0_DB: &[f64] = &[1.0, 1.5, 2.0, 2.5, 3.0, 3.5];
+    const SIMULATION_LIST_SIZE: usize = 64;
+    const SIMULATION_MAX_FRAMES: usize = 100000;
+    const SIMULATION_FRAME_ERRORS: usize = 100;
+    const SIMULATION_SEED: u64 = 2022;
+    // union bounds of the code (and of the DSO CRC's undetected errors) when run with "union_bounds",
+    // at the simulation Eb/N0 points over AWGN and at these crossover probabilities over the BSC
+    const BSC_CROSSOVER: &[f64] = &[0.02, 0.01, 0.005, 0.002, 0.001];
+    // (numerators, denominator) candidates of the joint CRC-ZTCC design when run with "joint_search"
+    // (run with "joint_search code_search" to take the best encoders of the code search instead)
+    const JOINT_SEARCH_ENCODERS: &[(&[u64], u64)] = &[(&[107, 135, 133], 141)];
     let args: Vec<String> = env::args().collect();
 
     let instant = Instant::now();
@@ -51,56 +116,334 @@ fn main() {
         aggregate: 0
     };
 
-    let trel = generate_feedback_trellis(V, NUMERATOR, DENOMINATOR);
+    // Joint design mode: find the DSO CRC of every candidate encoder and keep the best pair
+    if args.contains(&"joint_search".to_string()) {
+        // the joint design only builds feedback encoders, so the other code modes do not apply to it
+        for mode in ["poly2trellis", "rational", "rate_1_n", "punctured", "min_weight_terminations", "tail_biting"] {
+            if args.contains(&mode.to_string()) {
+                println!("warning: \"{}\" is ignored by the joint search, which only covers feedback encoders", mode);
+            }
+        }
+        let encoders: Vec<(Vec<u64>, u64)> = if args.contains(&"code_search".to_string()) {
+            ztcc_code_search(V, NUMERATOR.len(), TRELLIS_LEN, CODE_SEARCH_CANDIDATES).expect("couldn't search the codes").into_iter()
+                .map(|candidate| (candidate.numerators, candidate.denominator))
+                .collect()
+        } else {
+            JOINT_SEARCH_ENCODERS.iter().map(|(numerators, denominator)| (numerators.to_vec(), *denominator)).collect()
+        };
+        let pairs = joint_crc_ztcc_search(V, &encoders, MAX_SEARCH_DISTANCE, TRELLIS_LEN, TARGET_CRC_DEGREE);
+        let json = serde_json::to_string(&pairs).expect("couldn't serialize joint search to json");
+        fs::write(format!("{}/joint_search.json", PATH_STR), json).expect("couldn't write joint search json to file");
+        match pairs.first() {
+            Some(best) => println!("Best pair: numerators {:?}, denominator {}, CRC {:#x}, undetected spectrum {:?}",
+                best.numerators, best.denominator, best.crc, best.undetected_spectrum),
+            None => println!("No encoder had a unique DSO CRC within max_search_distance"),
+        }
+        return;
+    }
+
+    // Code search mode: rank every feedback encoder with the same v and k as the code above
+    if args.contains(&"code_search".to_string()) {
+        let candidates = ztcc_code_search(V, NUMERATOR.len(), TRELLIS_LEN, CODE_SEARCH_CANDIDATES).expect("couldn't search the codes");
+        for candidate in &candidates {
+            println!("numerators {:?}, denominator {}: d_free = {}, multiplicity = {}",
+                candidate.numerators, candidate.denominator, candidate.free_distance, candidate.multiplicity);
+        }
+        let json = serde_json::to_string(&candidates).expect("couldn't serialize code search to json");
+        fs::write(format!("{}/code_search.json", PATH_STR), json).expect("couldn't write code search json to file");
+        println!("Best encoder: numerators {:?}, denominator {}", candidates[0].numerators, candidates[0].denominator);
+        return;
+    }
+
+    // the gcd criterion only applies to the rate-1/n generators, before any puncturing
+    let mut gcd_catastrophic: Option<bool> = None;
+    let trel = if args.contains(&"poly2trellis".to_string()) {
+        let code_generator = Vec2d::new(CODE_GENERATOR.to_vec(), CONSTRAINT_LENGTHS.len(), CODE_GENERATOR.len() / CONSTRAINT_LENGTHS.len());
+        if FEEDBACK_CONNECTIONS.is_empty() {
+            poly2trellis(CONSTRAINT_LENGTHS, &code_generator).expect("couldn't generate the trellis")
+        } else {
+            poly2trellis_with_feedback(CONSTRAINT_LENGTHS, &code_generator, FEEDBACK_CONNECTIONS).expect("couldn't generate the trellis")
+        }
+    } else if args.contains(&"rational".to_string()) {
+        let num_outputs = RATIONAL_NUMERATORS.len() / RATIONAL_CONSTRAINT_LENGTHS.len();
+        RationalGeneratorMatrix::new(RATIONAL_CONSTRAINT_LENGTHS.to_vec(),
+            Vec2d::new(RATIONAL_NUMERATORS.to_vec(), RATIONAL_CONSTRAINT_LENGTHS.len(), num_outputs),
+            Vec2d::new(RATIONAL_DENOMINATORS.to_vec(), RATIONAL_CONSTRAINT_LENGTHS.len(), num_outputs))
+            .to_trellis().expect("couldn't generate the trellis")
+    } else if args.contains(&"rate_1_n".to_string()) {
+        if !args.contains(&"punctured".to_string()) {
+            gcd_catastrophic = Some(is_catastrophic_rate_1_n(RATE_1_N_CONSTRAINT_LENGTH, RATE_1_N_GENERATORS, RATE_1_N_FEEDBACK)
+                .expect("invalid generators"));
+        }
+        generate_rate_1_n_trellis(RATE_1_N_CONSTRAINT_LENGTH, RATE_1_N_GENERATORS, RATE_1_N_FEEDBACK).expect("couldn't generate the trellis")
+    } else {
+        generate_feedback_trellis(V, NUMERATOR, DENOMINATOR).expect("couldn't generate the trellis")
+    };
+    let mut trel = if args.contains(&"punctured".to_string()) {
+        let pattern = Vec2d::new(PUNCTURING_PATTERN.to_vec(), PUNCTURING_PATTERN.len() / PUNCTURING_PERIOD, PUNCTURING_PERIOD);
+        PuncturingPattern::new(pattern).puncture(&trel).expect("couldn't puncture the trellis")
+    } else {
+        trel
+    };
+
+    // Terminate with the minimum output weight sequences instead of the shortest ones
+    if args.contains(&"min_weight_terminations".to_string()) {
+        trel.use_min_weight_terminations().expect("couldn't terminate the trellis");
+    }
+
+    // The results below are cached in a directory of their own for every code, mode, trellis length
+    // and search distance, so that switching any of them never reads the files of another code
+    let mut code_key = if args.contains(&"poly2trellis".to_string()) {
+        format!("poly2trellis_K{}_G{}_F{}", join(CONSTRAINT_LENGTHS), join(&CODE_GENERATOR), join(FEEDBACK_CONNECTIONS))
+    } else if args.contains(&"rational".to_string()) {
+        format!("rational_K{}_num{}_den{}", join(RATIONAL_CONSTRAINT_LENGTHS), join(&RATIONAL_NUMERATORS), join(&RATIONAL_DENOMINATORS))
+    } else if args.contains(&"rate_1_n".to_string()) {
+        format!("rate_1_n_K{}_G{}_F{}", RATE_1_N_CONSTRAINT_LENGTH, join(RATE_1_N_GENERATORS), join(RATE_1_N_FEEDBACK.as_slice()))
+    } else {
+        format!("feedback_v{}_num{}_den{}", V, join(NUMERATOR), DENOMINATOR)
+    };
+    if args.contains(&"punctured".to_string()) {
+        let pattern: String = PUNCTURING_PATTERN.iter().map(|&kept| if kept { '1' } else { '0' }).collect();
+        code_key += &format!("_punctured{}", pattern);
+    }
+    if args.contains(&"min_weight_terminations".to_string()) {
+        code_key += "_min_weight_terminations";
+    }
+    if args.contains(&"truncated_spectrum".to_string()) {
+        code_key += "_truncated";
+    }
+    code_key += &format!("_len{}_d{}", TRELLIS_LEN, MAX_SEARCH_DISTANCE);
+    let results_path = format!("{}/{}", PATH_STR, code_key);
+    fs::create_dir_all(&results_path).expect("couldn't create the results directory");
+
+    // Catastrophic codes have zero-output cycles driven by nonzero inputs, which the path searches
+    // below would keep extending; run with "allow_catastrophic" to go ahead anyway. For a rate-1/n
+    // encoder the gcd of the generators cross-checks the trellis.
+    let trellis_catastrophic = is_catastrophic(&trel);
+    if gcd_catastrophic == Some(true) {
+        println!("catastrophic by the gcd criterion: the gcd of the generators, once the feedback is cancelled, is not a monomial");
+    }
+    if trellis_catastrophic {
+        println!("catastrophic by the trellis criterion: a zero-output cycle carries a nonzero input");
+    }
+    if gcd_catastrophic.is_some_and(|catastrophic| catastrophic != trellis_catastrophic) {
+        println!("warning: the gcd and trellis criteria disagree on whether the encoder is catastrophic");
+    }
+    let cycle = find_zero_output_cycle(&trel);
+    if (trellis_catastrophic || gcd_catastrophic == Some(true)) && !args.contains(&"allow_catastrophic".to_string()) {
+        panic!("the encoder is catastrophic (zero-output cycle through (state, input) {:?})", cycle);
+    }
+    if let Some(cycle) = cycle {
+        println!("warning: the trellis has a zero-output cycle through (state, input) {:?}", cycle);
+    }
+
+    let free_distance = find_free_distance(&trel);
+    println!("d_free = {}, multiplicity = {:?}", free_distance.free_distance, free_distance.multiplicity);
+    println!("column distance profile: {:?}", column_distance_profile(&trel, DISTANCE_PROFILE_COLUMNS));
+
+    // Closed-form transfer function T(D) of the error events and its first terms
+    if args.contains(&"transfer_function".to_string()) {
+        match find_transfer_function(&trel) {
+            Ok(transfer_function) => {
+                println!("T(D) numerator: {:?}", transfer_function.numerator[0]);
+                println!("T(D) denominator: {:?}", transfer_function.denominator[0]);
+                println!("T(D) series: {:?}", transfer_function.output_weight_series(TRANSFER_FUNCTION_DEGREE));
+            },
+            Err(error) => println!("no transfer function: {}", error),
+        }
+        // T(I, D): row w of the numerator, denominator and series holds the terms of I^w
+        match find_input_output_transfer_function(&trel) {
+            Ok(transfer_function) => {
+                println!("T(I, D) numerator: {:?}", transfer_function.numerator);
+                println!("T(I, D) denominator: {:?}", transfer_function.denominator);
+                println!("T(I, D) series: {:?}", transfer_function.series(TRANSFER_FUNCTION_DEGREE));
+            },
+            Err(error) => println!("no input-output transfer function: {}", error),
+        }
+    }
+
+    let k = trel.num_input_rails;
+    let mu = trel.termination_length;
+
+    // Tail-biting (TBCC) mode: the paths are enumerated directly from the trellis and no input
+    // bits are spent on termination, so the CRC search sees mu = 0
+    if args.contains(&"tail_biting".to_string()) {
+        if args.contains(&"tail_biting_paths".to_string()) || !Path::new(&format!("{}/tail_biting_paths.json", results_path)).exists() {
+            let tail_biting_paths = find_tail_biting_paths(MAX_SEARCH_DISTANCE, TRELLIS_LEN, &trel);
+            let json = serde_json::to_string(&tail_biting_paths).expect("couldn't serialize tbps to json");
+            fs::write(format!("{}/tail_biting_paths.json", results_path), json).expect("couldn't write tbp json to file");
+        }
+
+        if args.contains(&"weight_spectrum".to_string()) || !Path::new(&format!("{}/tbcc_weight_spectrum.json", results_path)).exists() {
+            let weight_spectrum = compute_tbcc_weight_spectrum(TRELLIS_LEN, trel).unwrap();
+            let json = weight_spectrum_to_json(&weight_spectrum);
+            fs::write(format!("{}/tbcc_weight_spectrum.json", results_path), json).expect("couldn't write weight spectrum json to file");
+        }
+
+        let tbps_string: String = json_from_file(&format!("{}/tail_biting_paths.json", results_path));
+        let tail_biting_paths: ZTPs = serde_json::from_str(&tbps_string).expect("couldn't read tbps from file");
+
+        dso_crc_polynomial_search(k, 0, MAX_SEARCH_DISTANCE, TRELLIS_LEN, TARGET_CRC_DEGREE, tail_biting_paths);
+
+        println!("elapsed time since start: {:?}", instant.elapsed());
+        return;
+    }
 
     // Generates error events if they don't exist or we want to regenerate them
-    if args.contains(&"error_events".to_string()) || !Path::new(&format!("{}/error_events.json", PATH_STR)).exists() {
-        error_events = find_irreducible_error_event(
-            V, NUMERATOR, DENOMINATOR, MAX_SEARCH_DISTANCE, &trel);
+    if args.contains(&"error_events".to_string()) || !Path::new(&format!("{}/error_events.json", results_path)).exists() {
+        error_events = find_irreducible_error_event(MAX_SEARCH_DISTANCE, &trel);
 
         let json = serde_json::to_string(&error_events).expect("couldn't serialize weight spectrum to json");
-        fs::write(format!("{}/error_events.json", PATH_STR), json).expect("couldn't write weight spectrum json to file");
+        fs::write(format!("{}/error_events.json", results_path), json).expect("couldn't write weight spectrum json to file");
     }
     
     // Generates weight spectrum if it doesn't exist or we want to regenerate them
-    if args.contains(&"weight_spectrum".to_string()) || !Path::new(&format!("{}/weight_spectrum.json", PATH_STR)).exists() {
-        let weight_spectrum = compute_ztcc_weight_spectrum_fast(
-            V,  NUMERATOR, DENOMINATOR, TRELLIS_LEN, trel).unwrap();
+    if args.contains(&"weight_spectrum".to_string()) || !Path::new(&format!("{}/weight_spectrum.json", results_path)).exists() {
+        // exact integer coefficients, since the multiplicities outgrow the 53-bit mantissa of f64;
+        // "squaring" takes powers of the transfer matrix instead, which is faster for very long trellises,
+        // and "truncated_spectrum" stops at MAX_SEARCH_DISTANCE, all the CRC search needs (the union
+        // bounds then only see those weights)
+        let squaring = args.contains(&"squaring".to_string());
+        let weight_spectrum = if args.contains(&"truncated_spectrum".to_string()) {
+            if squaring {
+                compute_ztcc_weight_spectrum_truncated_by_squaring(TRELLIS_LEN, trel.clone(), MAX_SEARCH_DISTANCE).unwrap()
+            } else {
+                compute_ztcc_weight_spectrum_truncated(TRELLIS_LEN, trel.clone(), MAX_SEARCH_DISTANCE).unwrap()
+            }
+        } else if squaring {
+            compute_ztcc_weight_spectrum_by_squaring(TRELLIS_LEN, trel.clone()).unwrap()
+        } else {
+            compute_ztcc_weight_spectrum(TRELLIS_LEN, trel.clone()).unwrap()
+        };
     
         // let encoded_poly = bincode::serialize(&weight_spectrum.coeffs()).expect("couldn't encode weight spectrum");
         // fs::write(format!("{}/weight_spectrum", PATH_STR), encoded_poly).expect("couldn't write weight spectrum to file");
-        let json = serde_json::to_string(&weight_spectrum.coeffs()).expect("couldn't serialize weight spectrum to json");
-        fs::write(format!("{}/weight_spectrum.json", PATH_STR), json).expect("couldn't write weight spectrum json to file");
+        let json = weight_spectrum_to_json(&weight_spectrum);
+        fs::write(format!("{}/weight_spectrum.json", results_path), json).expect("couldn't write weight spectrum json to file");
+    }
+
+    // The weight spectra of every trellis length up to TRELLIS_LEN, for sweeps over the information length
+    if args.contains(&"spectrum_table".to_string()) {
+        let max_distance = if args.contains(&"truncated_spectrum".to_string()) { MAX_SEARCH_DISTANCE } else { usize::MAX };
+        let table = compute_ztcc_weight_spectrum_table(TRELLIS_LEN, trel.clone(), max_distance).unwrap();
+        let json = serde_json::to_string(&table).expect("couldn't serialize weight spectrum table to json");
+        fs::write(format!("{}/weight_spectrum_table.json", results_path), json).expect("couldn't write weight spectrum table json to file");
+    }
+
+    // The input-output weight enumerator, up to the same distance as the weight spectrum
+    if args.contains(&"iowe".to_string()) {
+        let max_distance = if args.contains(&"truncated_spectrum".to_string()) { MAX_SEARCH_DISTANCE } else { usize::MAX };
+        let iowe = compute_ztcc_iowe(TRELLIS_LEN, trel.clone(), max_distance).unwrap();
+        fs::write(format!("{}/iowe.json", results_path), iowe_to_json(&iowe)).expect("couldn't write iowe json to file");
     }
 
-    let weight_spectrum_string: String = json_from_file(&format!("{}/weight_spectrum.json", PATH_STR));
-    let decoded_v: Vec<f64> = serde_json::from_str(&weight_spectrum_string).expect("couldn't read weight spectrum from file");
-    let weight_spectrum: Poly<f64> = Poly::new_from_coeffs(&decoded_v);
+    let weight_spectrum_string: String = json_from_file(&format!("{}/weight_spectrum.json", results_path));
+    let weight_spectrum: Vec<BigUint> = weight_spectrum_from_json(&weight_spectrum_string).expect("couldn't read weight spectrum from file");
+    let decoded_v: Vec<f64> = weight_spectrum_to_f64(&weight_spectrum);
 
-    let error_events_string: String = json_from_file(&format!("{}/error_events.json", PATH_STR));
+    let error_events_string: String = json_from_file(&format!("{}/error_events.json", results_path));
     let error_events: ErrorEvents = serde_json::from_str(&error_events_string).expect("couldn't read weight spectrum from file");
     // let decoded_v: Vec<usize> = bincode::deserialize(&read_v).expect("Couldn't deserialize weight spectrum");
     // let weight_spectrum = Poly::new_from_coeffs(&decoded_v);
     
-    if args.contains(&"reconstruct_ztps".to_string()) || !Path::new(&format!("{}/reconstruct_ztps.json", PATH_STR)).exists() {
-        zero_terminated_paths = reconstruct_ztps(V, NUMERATOR,
-            DENOMINATOR, MAX_SEARCH_DISTANCE, TRELLIS_LEN, weight_spectrum, error_events);
+    if args.contains(&"reconstruct_ztps".to_string()) || !Path::new(&format!("{}/reconstruct_ztps.json", results_path)).exists() {
+        zero_terminated_paths = reconstruct_ztps(k, MAX_SEARCH_DISTANCE, TRELLIS_LEN, weight_spectrum.clone(), error_events);
         let json = serde_json::to_string(&zero_terminated_paths).expect("couldn't serialize ztp to json");
-        fs::write(format!("{}/reconstruct_ztps.json", PATH_STR), json).expect("couldn't write ztp json to file");
+        fs::write(format!("{}/reconstruct_ztps.json", results_path), json).expect("couldn't write ztp json to file");
     }
     
-    let ztps_string: String = json_from_file(&format!("{}/reconstruct_ztps.json", PATH_STR));
+    let ztps_string: String = json_from_file(&format!("{}/reconstruct_ztps.json", results_path));
     let zero_terminated_paths: ZTPs = serde_json::from_str(&ztps_string).expect("couldn't read ztps from file");
     
 
     //println!("ztp[6,1]: {}", zero_terminated_paths.zero_terminated_paths[6][0]);
 
-    dso_crc_polynomial_search(V, NUMERATOR, DENOMINATOR, MAX_SEARCH_DISTANCE, TRELLIS_LEN, TARGET_CRC_DEGREE, zero_terminated_paths);
-    
+    let crc_polynomial = dso_crc_polynomial_search(k, mu, MAX_SEARCH_DISTANCE, TRELLIS_LEN, TARGET_CRC_DEGREE, zero_terminated_paths.clone());
+
+    // Union bounds from the stored weight spectrum. The frame and bit error bounds are for the ZTCC
+    // alone (all k*(N - mu) trellis inputs are message bits); the undetected error bound is for the
+    // ZTCC with the DSO CRC, so its rate counts the CRC bits as redundancy.
+    if args.contains(&"union_bounds".to_string()) {
+        let n = trel.num_output_symbols.trailing_zeros() as usize;
+        let num_input_bits = k * (usize::from(TRELLIS_LEN) - mu);
+        let num_code_bits = (n * usize::from(TRELLIS_LEN)) as f64;
+        let ztcc_rate = num_input_bits as f64 / num_code_bits;
+        let crc_rate = (num_input_bits - TARGET_CRC_DEGREE as usize) as f64 / num_code_bits;
+        // the bit error bound takes the IOWE from iowe.json when it was written (run with "iowe") and
+        // adds up to the weight spectrum
+        let iowe_file = format!("{}/iowe.json", results_path);
+        let iowe = if Path::new(&iowe_file).exists() {
+            Some(iowe_from_json(&json_from_file(&iowe_file)).expect("couldn't read iowe from file"))
+        } else {
+            None
+        };
+        let input_weighted_spectrum = match iowe {
+            Some(iowe) if iowe_matches_weight_spectrum(&iowe, &weight_spectrum) => input_weighted_spectrum_from_iowe(&iowe),
+            Some(_) => {
+                println!("iowe.json does not add up to the weight spectrum, recomputing the input-weighted spectrum");
+                compute_input_weighted_spectrum(TRELLIS_LEN, &trel)
+            },
+            None => compute_input_weighted_spectrum(TRELLIS_LEN, &trel),
+        };
+        // without a unique DSO CRC there is no undetected error bound, so the field is left out
+        let undetected_spectrum = match crc_polynomial {
+            Some(crc_polynomial) => Some(compute_undetected_spectrum(k, mu, MAX_SEARCH_DISTANCE, crc_polynomial, TARGET_CRC_DEGREE, zero_terminated_paths)),
+            None => {
+                println!("No DSO CRC was identified, leaving out the undetected error bound");
+                None
+            },
+        };
+
+        let union_bounds = |parameters: &[f64], channel: &dyn Fn(f64, f64) -> Channel| UnionBounds {
+            channel_parameters: parameters.to_vec(),
+            frame_error: parameters.iter().map(|&x| frame_error_union_bound(&decoded_v, channel(x, ztcc_rate))).collect(),
+            bit_error: parameters.iter().map(|&x| bit_error_union_bound(&input_weighted_spectrum, num_input_bits, channel(x, ztcc_rate))).collect(),
+            undetected_error: undetected_spectrum.as_ref().map(|undetected_spectrum| {
+                parameters.iter().map(|&x| undetected_error_union_bound(undetected_spectrum, channel(x, crc_rate))).collect()
+            }),
+        };
+        let awgn = union_bounds(EB_N0_DB, &|eb_n0_db, rate| Channel::Awgn { eb_n0_db, rate });
+        let bsc = union_bounds(BSC_CROSSOVER, &|crossover, _| Channel::Bsc { crossover });
+        let json = serde_json::to_string(&awgn).expect("couldn't serialize union bounds to json");
+        fs::write(format!("{}/union_bounds_awgn.json", results_path), json).expect("couldn't write union bounds json to file");
+        let json = serde_json::to_string(&bsc).expect("couldn't serialize union bounds to json");
+        fs::write(format!("{}/union_bounds_bsc.json", results_path), json).expect("couldn't write union bounds json to file");
+    }
+
+    // Simulates the CRC-aided list decoding of the code with the DSO CRC
+    if args.contains(&"simulate".to_string()) {
+        if let Some(crc_polynomial) = crc_polynomial {
+            // the DSO CRC is optimal for the MSB-first, interleaved layout of the ZTPs; "crc_lsb_first"
+            // and "crc_rail_by_rail" simulate the same generator with a reflected or rail-by-rail layout
+            let bit_order = if args.contains(&"crc_lsb_first".to_string()) { BitOrder::LsbFirst } else { BitOrder::MsbFirst };
+            let rail_mapping = if args.contains(&"crc_rail_by_rail".to_string()) { RailMapping::RailByRail } else { RailMapping::Interleaved };
+            let crc = CrcEncoder::new(crc_polynomial, bit_order, rail_mapping, k);
+            let decoder = if args.contains(&"parallel_list".to_string()) { ListDecoder::Parallel } else { ListDecoder::Serial };
+            let config = SimulationConfig {
+                eb_n0_db: EB_N0_DB.to_vec(),
+                decoder,
+                max_list_size: SIMULATION_LIST_SIZE,
+                max_frames: SIMULATION_MAX_FRAMES,
+                target_frame_errors: SIMULATION_FRAME_ERRORS,
+                seed: SIMULATION_SEED,
+            };
+            let points = simulate_fer(&trel, &crc, TRELLIS_LEN, &config);
+            let json = serde_json::to_string(&points).expect("couldn't serialize simulation results to json");
+            fs::write(format!("{}/fer_simulation.json", results_path), json).expect("couldn't write simulation json to file");
+        } else {
+            println!("No DSO CRC was identified, skipping the simulation");
+        }
+    }
+
     println!("elapsed time since start: {:?}", instant.elapsed());
 }
 
 fn json_from_file(file: &String) -> String {
     let blob: String = fs::read_to_string(file).expect(&format!("Couldn't read file {}", &file));
     blob
-}
\ No newline at end of file
+}
+
+// Joins the values with '-' for the name of a results directory
+fn join<T: std::fmt::Display>(values: &[T]) -> String {
+    values.iter().map(|value| value.to_string()).collect::<Vec<String>>().join("-")
+}
@@ -0,0 +1,177 @@
+use crate::trellis::trellis::Trellis;
+use crate::crc_encoder::CrcEncoder;
+use crate::viterbi_decoder::soft_decision_metric;
+use crate::viterbi_decoder::hard_decision_metric;
+use crate::serial_list_viterbi_decoder::ListDecodingResult;
+
+use bitvec::prelude::*;
+
+// One path of a list decoder: its metric and its k*N trellis inputs (without the termination)
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedPath {
+    pub metric: f64,
+    pub input: BitVec,
+}
+
+// Parallel list Viterbi decoding (PLVA) of a hard-decision received word, see parallel_list_viterbi_decode.
+// The simulations are soft-decision, so nothing in main decodes hard decisions.
+#[allow(dead_code)]
+pub fn parallel_list_viterbi_decode_hard(received: &BitSlice, trellis: &Trellis, list_size: usize) -> Vec<DecodedPath> {
+    let (num_sections, branch_metric) = hard_decision_metric(received, trellis);
+    parallel_list_viterbi_decode(num_sections, trellis, list_size, branch_metric)
+}
+
+// Parallel list Viterbi decoding (PLVA) of BPSK observations or LLRs, see parallel_list_viterbi_decode
+pub fn parallel_list_viterbi_decode_soft(received: &[f64], trellis: &Trellis, list_size: usize) -> Vec<DecodedPath> {
+    let (num_sections, branch_metric) = soft_decision_metric(received, trellis);
+    parallel_list_viterbi_decode(num_sections, trellis, list_size, branch_metric)
+}
+
+// CRC-aided selection from a PLVA list: the first path (in metric order) that passes the CRC.
+// The list size reported is the number of paths checked, as for serial list decoding.
+pub fn select_crc_path(paths: &[DecodedPath], crc: &CrcEncoder) -> ListDecodingResult {
+    for (rank, path) in paths.iter().enumerate() {
+        if crc.check(&path.input) {
+            return ListDecodingResult { decoded: Some(path.input.clone()), list_size: rank + 1 };
+        }
+    }
+    ListDecodingResult { decoded: None, list_size: paths.len() }
+}
+
+// A survivor: its metric and where it came from (previous state, rank of the survivor there, input)
+#[derive(Debug, Clone, Copy)]
+struct Survivor {
+    metric: f64,
+    pre_state: usize,
+    pre_rank: usize,
+    input: usize,
+}
+
+// Keeps the list_size best survivors into every state at every section, so the list_size best
+// paths into state 0 at the end come out together in one forward pass, best first. As in
+// encode_ztcc, the last mu transitions are the termination of the state reached after the
+// message, so the list holds list_size distinct messages (fewer if the code has fewer).
+fn parallel_list_viterbi_decode(num_sections: usize, trellis: &Trellis, list_size: usize,
+    branch_metric: impl Fn(usize, u64) -> f64) -> Vec<DecodedPath> {
+
+    let k = trellis.num_input_rails;
+    let num_states = trellis.num_states;
+    assert!(list_size > 0, "the list size must be positive");
+    assert!(num_sections >= trellis.termination_length, "received word is shorter than the termination");
+    let num_message_sections = num_sections - trellis.termination_length;
+
+    // survivors[section][state], sorted by metric
+    let mut survivors: Vec<Vec<Vec<Survivor>>> = Vec::with_capacity(num_message_sections + 1);
+    let mut initial: Vec<Vec<Survivor>> = vec![vec![]; num_states];
+    initial[0].push(Survivor { metric: 0.0, pre_state: 0, pre_rank: 0, input: 0 });
+    survivors.push(initial);
+
+    for section in 0..num_message_sections {
+        let mut next_survivors: Vec<Vec<Survivor>> = vec![vec![]; num_states];
+        for (state, state_survivors) in survivors[section].iter().enumerate() {
+            for input in 0..trellis.num_input_symbols {
+                let next_state = *trellis.next_states.index(state, input);
+                let metric = branch_metric(section, *trellis.outputs.index(state, input));
+                for (rank, survivor) in state_survivors.iter().enumerate() {
+                    next_survivors[next_state].push(Survivor { metric: survivor.metric + metric, pre_state: state, pre_rank: rank, input });
+                }
+            }
+        }
+        for state_survivors in next_survivors.iter_mut() {
+            keep_best(state_survivors, list_size);
+        }
+        survivors.push(next_survivors);
+    }
+
+    // the termination takes every state after the message to state 0 in one step
+    let mut finals: Vec<Survivor> = vec![];
+    for (state, state_survivors) in survivors[num_message_sections].iter().enumerate() {
+        let termination_metric: f64 = trellis.termination_outputs[state].iter().enumerate()
+            .map(|(j, &output)| branch_metric(num_message_sections + j, output))
+            .sum();
+        for (rank, survivor) in state_survivors.iter().enumerate() {
+            finals.push(Survivor { metric: survivor.metric + termination_metric, pre_state: state, pre_rank: rank, input: 0 });
+        }
+    }
+    keep_best(&mut finals, list_size);
+
+    finals.iter().map(|last| {
+        let mut inputs: Vec<usize> = vec![0; num_message_sections];
+        let (mut state, mut rank) = (last.pre_state, last.pre_rank);
+        for section in (0..num_message_sections).rev() {
+            let survivor = survivors[section + 1][state][rank];
+            inputs[section] = survivor.input;
+            state = survivor.pre_state;
+            rank = survivor.pre_rank;
+        }
+
+        let mut input: BitVec = BitVec::with_capacity(k * num_message_sections);
+        for symbol in inputs {
+            for j in (0..k).rev() {
+                input.push((symbol >> j) & 1 == 1);
+            }
+        }
+        DecodedPath { metric: last.metric, input }
+    }).collect()
+}
+
+// Sorts the survivors by metric (keeping the order of equal metrics) and drops all but list_size
+fn keep_best(survivors: &mut Vec<Survivor>, list_size: usize) {
+    survivors.sort_by(|a, b| a.metric.total_cmp(&b.metric));
+    survivors.truncate(list_size);
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::parallel_list_viterbi_decoder::{parallel_list_viterbi_decode_hard, parallel_list_viterbi_decode_soft, select_crc_path};
+    use crate::serial_list_viterbi_decoder::serial_list_viterbi_decode_soft;
+    use crate::viterbi_decoder::viterbi_decode_hard;
+    use crate::crc_encoder::{CrcEncoder, BitOrder, RailMapping};
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+
+    #[test]
+    fn test_list_matches_brute_force() {
+        // all 16 messages of 4 bits, ranked by Hamming distance between codeword and received word
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let received = bitvec![1, 1, 0, 1, 0, 0, 1, 0, 1, 1, 0, 1];
+        let mut distances: Vec<f64> = (0..16_usize).map(|m| {
+            let message: BitVec = (0..4).rev().map(|j| (m >> j) & 1 == 1).collect();
+            (encode_ztcc(&message, &trellis) ^ received.clone()).count_ones() as f64
+        }).collect();
+        distances.sort_by(|a, b| a.total_cmp(b));
+
+        let paths = parallel_list_viterbi_decode_hard(&received, &trellis, 20);
+        assert_eq!(paths.len(), 16);
+        assert_eq!(paths.iter().map(|path| path.metric).collect::<Vec<f64>>(), distances);
+        for path in &paths {
+            let distance = (encode_ztcc(&path.input, &trellis) ^ received.clone()).count_ones() as f64;
+            assert_eq!(distance, path.metric);
+        }
+        assert_eq!(paths[0].input, viterbi_decode_hard(&received, &trellis));
+    }
+
+    #[test]
+    fn test_crc_selection_agrees_with_serial_decoding() {
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        let crc = CrcEncoder::new(0x709, BitOrder::MsbFirst, RailMapping::Interleaved, 3);
+        let input = crc.encode(&bitvec![0; 3 * 12 - 10]);
+        // push a few all-zero codeword symbols towards 1 so that the best path is wrong
+        let mut received: Vec<f64> = encode_ztcc(&input, &trellis).iter().map(|bit| if *bit { -1.0 } else { 1.0 }).collect();
+        for position in [4, 5, 6, 9, 13, 21] {
+            received[position] = -0.8;
+        }
+        let paths = parallel_list_viterbi_decode_soft(&received, &trellis, 64);
+        let parallel = select_crc_path(&paths, &crc);
+        let serial = serial_list_viterbi_decode_soft(&received, &trellis, &crc, 64);
+        assert_eq!(parallel.decoded, serial.decoded);
+        assert_eq!(parallel.decoded.as_deref(), Some(input.as_bitslice()));
+        assert!(paths.windows(2).all(|pair| pair[0].metric <= pair[1].metric));
+    }
+}
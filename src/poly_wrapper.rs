@@ -117,6 +117,16 @@ impl BigUintToWrapped for BigUint {
     }
 }
 
+pub trait WrappedToBigUint {
+    fn unwrap(self) -> BigUint;
+}
+
+impl WrappedToBigUint for BigUintWrapper {
+    fn unwrap(self) -> BigUint {
+        self.0
+    }
+}
+
 impl Add for BigUintWrapper {
     type Output = BigUintWrapper;
 
@@ -0,0 +1,82 @@
+// Seeded pseudo-random number generator for reproducible simulations: xoshiro256** by Blackman and
+// Vigna, with the state filled from the seed by splitmix64, and Box-Muller for Gaussian samples.
+#[derive(Debug, Clone)]
+pub struct Random {
+    state: [u64; 4],
+    spare_gaussian: Option<f64>,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        let mut splitmix = seed;
+        let mut state = [0_u64; 4];
+        for word in state.iter_mut() {
+            splitmix = splitmix.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = splitmix;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            *word = z ^ (z >> 31);
+        }
+        Random { state, spare_gaussian: None }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let result = self.state[1].wrapping_mul(5).rotate_left(7).wrapping_mul(9);
+        let t = self.state[1] << 17;
+        self.state[2] ^= self.state[0];
+        self.state[3] ^= self.state[1];
+        self.state[1] ^= self.state[2];
+        self.state[0] ^= self.state[3];
+        self.state[2] ^= t;
+        self.state[3] = self.state[3].rotate_left(45);
+        result
+    }
+
+    // Uniform on [0, 1) with 53 random bits
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1_u64 << 53) as f64)
+    }
+
+    pub fn next_bit(&mut self) -> bool {
+        self.next_u64() >> 63 == 1
+    }
+
+    // Standard normal sample; Box-Muller gives two at a time, so every other call is free
+    pub fn next_gaussian(&mut self) -> f64 {
+        if let Some(gaussian) = self.spare_gaussian.take() {
+            return gaussian;
+        }
+        // 1 - u is in (0, 1], so the logarithm stays finite
+        let radius = (-2.0 * (1.0 - self.next_f64()).ln()).sqrt();
+        let angle = 2.0 * std::f64::consts::PI * self.next_f64();
+        self.spare_gaussian = Some(radius * angle.sin());
+        radius * angle.cos()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::random::Random;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Random::new(2022);
+        let mut b = Random::new(2022);
+        let mut c = Random::new(2023);
+        let sequence: Vec<u64> = (0..8).map(|_| a.next_u64()).collect();
+        assert_eq!(sequence, (0..8).map(|_| b.next_u64()).collect::<Vec<u64>>());
+        assert_ne!(sequence, (0..8).map(|_| c.next_u64()).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_gaussian_moments() {
+        let mut random = Random::new(7);
+        let samples: Vec<f64> = (0..200_000).map(|_| random.next_gaussian()).collect();
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance = samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        assert!(mean.abs() < 0.01);
+        assert!((variance - 1.0).abs() < 0.01);
+    }
+}
@@ -1,6 +1,6 @@
 
 
-use polynomen::Poly;
+use num_bigint::BigUint;
 use bitvec::prelude::*;
 
 use std::fs::File;
@@ -9,7 +9,7 @@ use std::io::{BufWriter, Write};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZTPs {
     pub zero_terminated_paths: Vec<Vec<BitVec>>,
     pub aggregate: usize
@@ -22,12 +22,9 @@ use crate::find_irreducible_error_event::ErrorEvents;
 //  not adapt to the high-rate ZTCCs due to nontrivial terminations.
 
 //  Input parameters:
-//    1) v: (v-1) denotes # memory elements in systematic feedback encoder.
-//    2) numerators: a 1-by-k row vector with entries in octal, where k
-//    denotes the # input rails
-//    3) denominator: a scalar in octal
-//    4) d_tilde: a scalar denoting the distance threshold (achievable)
-//    5) N: a scalar denoting the primal trellis length
+//    1) k: the # input rails of the encoder
+//    2) d_tilde: a scalar denoting the distance threshold (achievable)
+//    3) N: a scalar denoting the primal trellis length
 
 //  Output parameters: ZTP_node a struct composed of following fields
 //    1) list: a d_tilde-by-1 column vector denoting the list of length-kN
@@ -45,17 +42,16 @@ use crate::find_irreducible_error_event::ErrorEvents;
 
 //  Written by Hengjie Yang (hengjie.yang@ucla.edu)   04/17/21
 pub fn reconstruct_ztps(
-    _v: u16, numerators: [u16; 3], _octal_denominator: u16,
-    max_search_distance: usize, trellis_len: u16,
-    weight_spectrum: Poly<f64>, err_events: ErrorEvents) -> ZTPs {
+    k: usize, max_search_distance: usize, trellis_len: u16,
+    weight_spectrum: Vec<BigUint>, err_events: ErrorEvents) -> ZTPs {
     
-    let k = numerators.len() as u32;
+    let k = k as u32;
     
-    if max_search_distance > weight_spectrum.degree().unwrap() {
+    if max_search_distance >= weight_spectrum.len() {
         println!("max_search_distance ({}) is larger than weight_spectrum degree ({})",
-                max_search_distance, weight_spectrum.degree().unwrap());
+                max_search_distance, weight_spectrum.len().saturating_sub(1));
         panic!("max_search_distance ({}) is larger than weight_spectrum degree ({})",
-        max_search_distance, weight_spectrum.degree().unwrap())
+        max_search_distance, weight_spectrum.len().saturating_sub(1))
     }
 
     let mut zero_terminated_paths: Vec<Vec<BitVec>> = vec![vec![]; max_search_distance + 1];
@@ -126,7 +122,7 @@ pub fn reconstruct_ztps(
 
     let mut need_shift = false;
     for distance in 0..(max_search_distance + 1) {
-        if zero_terminated_paths[distance].len() != weight_spectrum[distance] as usize {
+        if BigUint::from(zero_terminated_paths[distance].len()) != weight_spectrum[distance] {
             need_shift = true;
             break;
         }
@@ -0,0 +1,260 @@
+use crate::trellis::trellis::Trellis;
+use crate::crc_encoder::CrcEncoder;
+use crate::viterbi_decoder::soft_decision_metric;
+use crate::viterbi_decoder::hard_decision_metric;
+
+use bitvec::prelude::*;
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+// What CRC-aided list decoding did with one received word
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListDecodingResult {
+    // the k*N trellis inputs (message and CRC, without the termination) of the first path in the
+    // list that passes the CRC, or None if no path up to the maximum list size passed
+    pub decoded: Option<BitVec>,
+    // the number of paths that were checked against the CRC
+    pub list_size: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListDecodingOutcome {
+    Correct,
+    // a path passed the CRC but is not the transmitted one
+    UndetectedError,
+    // no path in the list passed the CRC, so the frame is known to be in error
+    NoCrcMatch,
+}
+
+impl ListDecodingResult {
+    pub fn outcome(&self, transmitted_input: &BitSlice) -> ListDecodingOutcome {
+        match &self.decoded {
+            Some(decoded) if decoded.as_bitslice() == transmitted_input => ListDecodingOutcome::Correct,
+            Some(_) => ListDecodingOutcome::UndetectedError,
+            None => ListDecodingOutcome::NoCrcMatch,
+        }
+    }
+}
+
+// Serial list Viterbi decoding (S-LVD) of a hard-decision received word, see serial_list_viterbi_decode.
+// The simulations are soft-decision, so nothing in main decodes hard decisions.
+#[allow(dead_code)]
+pub fn serial_list_viterbi_decode_hard(received: &BitSlice, trellis: &Trellis, crc: &CrcEncoder, max_list_size: usize) -> ListDecodingResult {
+    let (num_sections, branch_metric) = hard_decision_metric(received, trellis);
+    serial_list_viterbi_decode(num_sections, trellis, crc, max_list_size, branch_metric)
+}
+
+// Serial list Viterbi decoding (S-LVD) of BPSK observations or LLRs, see serial_list_viterbi_decode
+pub fn serial_list_viterbi_decode_soft(received: &[f64], trellis: &Trellis, crc: &CrcEncoder, max_list_size: usize) -> ListDecodingResult {
+    let (num_sections, branch_metric) = soft_decision_metric(received, trellis);
+    serial_list_viterbi_decode(num_sections, trellis, crc, max_list_size, branch_metric)
+}
+
+// A partial path from (section, state) to the end of the trellis: the input of the branch leaving
+// (section, state), and the node where that branch ends (None for the termination)
+struct SuffixNode {
+    section: usize,
+    state: usize,
+    input: usize,
+    suffix_metric: f64,
+    next: Option<usize>,
+}
+
+// Heap entry ordered so that the smallest total metric comes out first
+struct Candidate {
+    total_metric: f64,
+    node: usize,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.total_metric.total_cmp(&self.total_metric).then(other.node.cmp(&self.node))
+    }
+}
+
+// Produces the paths from state 0 to state 0 in order of increasing metric and returns the first
+// one whose k*N message transitions pass the CRC, giving up after max_list_size (at least 1) paths.
+
+// The forward Viterbi pass keeps the best metric of every state at every section. The paths are
+// then grown backwards from the end (the tree-trellis algorithm): a suffix ending the path from
+// (section, state) is ranked by its own metric plus the best forward metric into (section, state),
+// which is exactly the metric of the best path containing that suffix, so complete paths come out
+// of the priority queue in order. The last mu transitions are the termination of the state reached
+// after the message, as in encode_ztcc, so every path in the list is a distinct message.
+fn serial_list_viterbi_decode(num_sections: usize, trellis: &Trellis, crc: &CrcEncoder, max_list_size: usize,
+    branch_metric: impl Fn(usize, u64) -> f64) -> ListDecodingResult {
+
+    let k = trellis.num_input_rails;
+    let num_states = trellis.num_states;
+    assert!(max_list_size > 0, "the maximum list size must be positive");
+    assert!(num_sections >= trellis.termination_length, "received word is shorter than the termination");
+    let num_message_sections = num_sections - trellis.termination_length;
+
+    // forward_metrics[section][state]: best metric from state 0 at section 0 into state at section
+    let mut forward_metrics: Vec<Vec<f64>> = vec![vec![f64::INFINITY; num_states]; num_message_sections + 1];
+    forward_metrics[0][0] = 0.0;
+    for section in 0..num_message_sections {
+        for state in 0..num_states {
+            let metric = forward_metrics[section][state];
+            if metric == f64::INFINITY {
+                continue;
+            }
+            for input in 0..trellis.num_input_symbols {
+                let next_state = *trellis.next_states.index(state, input);
+                let candidate = metric + branch_metric(section, *trellis.outputs.index(state, input));
+                if candidate < forward_metrics[section + 1][next_state] {
+                    forward_metrics[section + 1][next_state] = candidate;
+                }
+            }
+        }
+    }
+
+    let mut pre_branches: Vec<Vec<(usize, usize)>> = vec![vec![]; num_states];
+    for state in 0..num_states {
+        for input in 0..trellis.num_input_symbols {
+            pre_branches[*trellis.next_states.index(state, input)].push((state, input));
+        }
+    }
+
+    // every state reached after the message starts a suffix made of its termination
+    let mut nodes: Vec<SuffixNode> = vec![];
+    let mut queue: BinaryHeap<Candidate> = BinaryHeap::new();
+    for (state, &forward_metric) in forward_metrics[num_message_sections].iter().enumerate() {
+        if forward_metric == f64::INFINITY {
+            continue;
+        }
+        let termination_metric: f64 = trellis.termination_outputs[state].iter().enumerate()
+            .map(|(j, &output)| branch_metric(num_message_sections + j, output))
+            .sum();
+        nodes.push(SuffixNode { section: num_message_sections, state, input: 0, suffix_metric: termination_metric, next: None });
+        queue.push(Candidate { total_metric: forward_metric + termination_metric, node: nodes.len() - 1 });
+    }
+    let mut list_size: usize = 0;
+
+    while let Some(Candidate { node, .. }) = queue.pop() {
+        let (section, state, suffix_metric) = (nodes[node].section, nodes[node].state, nodes[node].suffix_metric);
+
+        if section == 0 {
+            // a complete path, read the inputs off the suffix chain
+            list_size += 1;
+            let mut decoded: BitVec = BitVec::with_capacity(k * num_message_sections);
+            let mut current = node;
+            while nodes[current].section < num_message_sections {
+                let input = nodes[current].input;
+                for j in (0..k).rev() {
+                    decoded.push((input >> j) & 1 == 1);
+                }
+                current = nodes[current].next.unwrap();
+            }
+            if crc.check(&decoded) {
+                return ListDecodingResult { decoded: Some(decoded), list_size };
+            }
+            if list_size == max_list_size {
+                break;
+            }
+            continue;
+        }
+
+        for &(pre_state, input) in &pre_branches[state] {
+            let forward_metric = forward_metrics[section - 1][pre_state];
+            if forward_metric == f64::INFINITY {
+                continue;
+            }
+            let metric = suffix_metric + branch_metric(section - 1, *trellis.outputs.index(pre_state, input));
+            nodes.push(SuffixNode { section: section - 1, state: pre_state, input, suffix_metric: metric, next: Some(node) });
+            queue.push(Candidate { total_metric: forward_metric + metric, node: nodes.len() - 1 });
+        }
+    }
+
+    ListDecodingResult { decoded: None, list_size }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::serial_list_viterbi_decoder::{serial_list_viterbi_decode_hard, serial_list_viterbi_decode_soft, ListDecodingOutcome};
+    use crate::viterbi_decoder::viterbi_decode_soft;
+    use crate::crc_encoder::{CrcEncoder, BitOrder, RailMapping};
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+
+    // a fixed pseudo-random message
+    fn message(len: usize) -> BitVec {
+        let mut x: u32 = 0x3c5a;
+        (0..len).map(|_| { x ^= x << 13; x ^= x >> 17; x ^= x << 5; x & 1 == 1 }).collect()
+    }
+
+    #[test]
+    fn test_first_path_is_viterbi_path() {
+        // with a degree-1 CRC (even parity) on a word the plain Viterbi decoder gets right,
+        // the list decoder stops at the first path
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        let crc = CrcEncoder::new(0b11, BitOrder::MsbFirst, RailMapping::Interleaved, 3);
+        let input = crc.encode(&message(3 * 16 - 1));
+        let received: Vec<f64> = encode_ztcc(&input, &trellis).iter().map(|bit| if *bit { -1.0 } else { 1.0 }).collect();
+        let result = serial_list_viterbi_decode_soft(&received, &trellis, &crc, 8);
+        assert_eq!(result.list_size, 1);
+        assert_eq!(result.decoded.as_deref(), Some(viterbi_decode_soft(&received, &trellis).as_bitslice()));
+        assert_eq!(result.outcome(&input), ListDecodingOutcome::Correct);
+    }
+
+    #[test]
+    fn test_crc_rescues_wrong_viterbi_path() {
+        // flipping all five bits of the weight-5 codeword of input 1 makes the all-zero word and
+        // that codeword equally likely, and the CRC picks the transmitted one further down the list
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let crc = CrcEncoder::new(0x709, BitOrder::MsbFirst, RailMapping::Interleaved, 1);
+        let input = crc.encode(&bitvec![0; 20]);
+        let mut received = encode_ztcc(&input, &trellis);
+        for position in [10, 11, 12, 15, 16, 17] {
+            let flipped = !received[position];
+            received.set(position, flipped);
+        }
+        let result = serial_list_viterbi_decode_hard(&received, &trellis, &crc, 64);
+        assert!(result.list_size > 1);
+        assert_eq!(result.outcome(&input), ListDecodingOutcome::Correct);
+
+        let result = serial_list_viterbi_decode_hard(&received, &trellis, &crc, 1);
+        assert_eq!(result.list_size, 1);
+        assert_eq!(result.outcome(&input), ListDecodingOutcome::NoCrcMatch);
+    }
+
+    #[test]
+    fn test_paths_come_out_in_metric_order() {
+        // a degree-10 CRC only passes the all-zero 4-bit input, so the list size is the rank of the
+        // all-zero path among the 16 messages, by Hamming distance to the received word
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let crc = CrcEncoder::new(0x709, BitOrder::MsbFirst, RailMapping::Interleaved, 1);
+        let received = encode_ztcc(bits![1, 1, 0, 1], &trellis);
+        let distances: Vec<usize> = (0..16_usize).map(|m| {
+            let message: BitVec = (0..4).rev().map(|j| (m >> j) & 1 == 1).collect();
+            (encode_ztcc(&message, &trellis) ^ received.clone()).count_ones()
+        }).collect();
+        let closer = distances.iter().filter(|&&d| d < distances[0]).count();
+        let not_farther = distances.iter().filter(|&&d| d <= distances[0]).count();
+
+        let result = serial_list_viterbi_decode_hard(&received, &trellis, &crc, 16);
+        assert_eq!(result.decoded, Some(bitvec![0; 4]));
+        assert!(result.list_size > closer && result.list_size <= not_farther);
+    }
+}
@@ -0,0 +1,146 @@
+use crate::trellis::trellis::Trellis;
+use crate::crc_encoder::CrcEncoder;
+use crate::encode_ztcc::encode_ztcc;
+use crate::serial_list_viterbi_decoder::{serial_list_viterbi_decode_soft, ListDecodingOutcome};
+use crate::parallel_list_viterbi_decoder::{parallel_list_viterbi_decode_soft, select_crc_path};
+use crate::random::Random;
+
+use bitvec::prelude::*;
+use rayon::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ListDecoder {
+    Serial,
+    Parallel,
+}
+
+// What to simulate: the Eb/N0 points in dB (Eb is the energy per message bit), the list decoder and
+// its list size, and when to stop each point (after max_frames frames or once target_frame_errors
+// frame errors have been seen). Every point uses its own generator seeded from seed and its index,
+// so the results are reproducible even though the points run in parallel.
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub eb_n0_db: Vec<f64>,
+    pub decoder: ListDecoder,
+    pub max_list_size: usize,
+    pub max_frames: usize,
+    pub target_frame_errors: usize,
+    pub seed: u64,
+}
+
+// Monte Carlo results at one Eb/N0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationPoint {
+    pub eb_n0_db: f64,
+    pub frames: usize,
+    pub undetected_errors: usize,
+    pub erasures: usize,
+    pub frame_error_rate: f64,
+    pub undetected_error_rate: f64,
+    pub erasure_rate: f64,
+    pub average_list_size: f64,
+}
+
+//   This function simulates CRC-aided list decoding of a ZTCC with BPSK over AWGN.
+
+//   Every frame draws k*(N - mu) - m random message bits, appends the m CRC bits, encodes the
+//   k*(N - mu) trellis inputs with the ZTCC (N trellis sections with the termination, the length
+//   used by the ZTP and DSO CRC stages), sends the n*N code bits as +1/-1 with noise variance
+//   1 / (2 R Eb/N0), and list decodes the observations. A frame is an undetected error if the
+//   decoder returns a wrong path that passes the CRC, and an erasure if no path in the list passes.
+
+//   Inputs:
+//       1) trellis, crc: the ZTCC and the CRC (its k must match the trellis)
+//       2) trellis_len: the trellis length N
+//       3) config: the Eb/N0 points, the decoder and the stopping rule
+
+//   Outputs: one SimulationPoint per Eb/N0
+pub fn simulate_fer(trellis: &Trellis, crc: &CrcEncoder, trellis_len: u16, config: &SimulationConfig) -> Vec<SimulationPoint> {
+
+    let k = trellis.num_input_rails;
+    let n = trellis.num_output_symbols.trailing_zeros() as usize;
+    let mu = trellis.termination_length;
+    assert_eq!(crc.k, k, "the CRC is laid out on {} rails but the trellis has {}", crc.k, k);
+    assert!(usize::from(trellis_len) > mu, "trellis length {} leaves no room for a message", trellis_len);
+
+    let num_input_bits = k * (usize::from(trellis_len) - mu);
+    assert!(num_input_bits > crc.degree(), "the CRC does not fit in the {} trellis inputs", num_input_bits);
+    let num_message_bits = num_input_bits - crc.degree();
+    let rate = num_message_bits as f64 / (n * usize::from(trellis_len)) as f64;
+
+    config.eb_n0_db.par_iter().enumerate().map(|(index, &point)| {
+        let mut random = Random::new(config.seed ^ (index as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let sigma = (1.0 / (2.0 * rate * 10_f64.powf(point / 10.0))).sqrt();
+
+        let (mut frames, mut undetected_errors, mut erasures, mut total_list_size) = (0, 0, 0, 0);
+        while frames < config.max_frames && undetected_errors + erasures < config.target_frame_errors {
+            let message: BitVec = (0..num_message_bits).map(|_| random.next_bit()).collect();
+            let input = crc.encode(&message);
+            let received: Vec<f64> = encode_ztcc(&input, trellis).iter()
+                .map(|bit| (if *bit { -1.0 } else { 1.0 }) + sigma * random.next_gaussian())
+                .collect();
+
+            let result = match config.decoder {
+                ListDecoder::Serial => serial_list_viterbi_decode_soft(&received, trellis, crc, config.max_list_size),
+                ListDecoder::Parallel => select_crc_path(&parallel_list_viterbi_decode_soft(&received, trellis, config.max_list_size), crc),
+            };
+            match result.outcome(&input) {
+                ListDecodingOutcome::Correct => (),
+                ListDecodingOutcome::UndetectedError => undetected_errors += 1,
+                ListDecodingOutcome::NoCrcMatch => erasures += 1,
+            }
+            total_list_size += result.list_size;
+            frames += 1;
+        }
+
+        println!("Eb/N0 = {} dB: {} frames, {} undetected errors, {} erasures", point, frames, undetected_errors, erasures);
+        SimulationPoint {
+            eb_n0_db: point,
+            frames,
+            undetected_errors,
+            erasures,
+            frame_error_rate: (undetected_errors + erasures) as f64 / frames as f64,
+            undetected_error_rate: undetected_errors as f64 / frames as f64,
+            erasure_rate: erasures as f64 / frames as f64,
+            average_list_size: total_list_size as f64 / frames as f64,
+        }
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::simulate_fer::{simulate_fer, ListDecoder, SimulationConfig};
+    use crate::crc_encoder::{CrcEncoder, BitOrder, RailMapping};
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_fer_falls_with_snr() {
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let crc = CrcEncoder::new(0b1011, BitOrder::MsbFirst, RailMapping::Interleaved, 1);
+        let mut config = SimulationConfig {
+            eb_n0_db: vec![0.0, 6.0],
+            decoder: ListDecoder::Serial,
+            max_list_size: 4,
+            max_frames: 300,
+            target_frame_errors: 300,
+            seed: 1,
+        };
+        let points = simulate_fer(&trellis, &crc, 32, &config);
+        assert!(points[0].frame_error_rate > points[1].frame_error_rate);
+        assert!(points[0].average_list_size >= points[1].average_list_size);
+        assert!(points.iter().all(|point| point.average_list_size >= 1.0 && point.average_list_size <= 4.0));
+
+        // the same seed gives the same results, with either decoder finding the same paths
+        config.decoder = ListDecoder::Parallel;
+        let again = simulate_fer(&trellis, &crc, 32, &config);
+        for (a, b) in points.iter().zip(&again) {
+            assert_eq!(a.frames, b.frames);
+            assert_eq!(a.undetected_errors + a.erasures, b.undetected_errors + b.erasures);
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::trellis::trellis::{Trellis, TrellisError, octal_to_binary, reverse_bits, gf2_gcd, gf2_degree};
+
+use ::gf256::p64;
+
+// Looks for a cycle of zero-output branches other than the all-zero self-loop of state 0.
+// A path caught in such a cycle never remerges with the all-zero path while its weight stays
+// bounded, so the IEE search keeps finding ever longer paths of the same weight instead of
+// terminating. This happens for catastrophic encoders (see is_catastrophic) and for non-minimal
+// realizations with unobservable states, and should be checked before any expensive stage.
+//
+// Returns the (state, input) branches of one such cycle, or None if there is none.
+pub fn find_zero_output_cycle(trellis: &Trellis) -> Option<Vec<(usize, usize)>> {
+
+    let num_states = trellis.num_states;
+
+    // depth-first search over the zero-output branches: 0 unvisited, 1 on the stack, 2 finished
+    let mut color: Vec<u8> = vec![0; num_states];
+    let mut parent: Vec<(usize, usize)> = vec![(0, 0); num_states];
+
+    for root in 0..num_states {
+        if color[root] != 0 {
+            continue;
+        }
+        color[root] = 1;
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+
+        while let Some(top) = stack.last_mut() {
+            let (state, input) = *top;
+            if input == trellis.num_input_symbols {
+                color[state] = 2;
+                stack.pop();
+                continue;
+            }
+            top.1 += 1;
+
+            if !is_zero_output_branch(trellis, state, input) {
+                continue;
+            }
+            let next_state = *trellis.next_states.index(state, input);
+            match color[next_state] {
+                0 => {
+                    color[next_state] = 1;
+                    parent[next_state] = (state, input);
+                    stack.push((next_state, 0));
+                },
+                1 => {
+                    // next_state is on the stack, so walk back from state to it
+                    let mut cycle: Vec<(usize, usize)> = vec![(state, input)];
+                    let mut current = state;
+                    while current != next_state {
+                        let branch = parent[current];
+                        cycle.push(branch);
+                        current = branch.0;
+                    }
+                    cycle.reverse();
+                    return Some(cycle);
+                },
+                _ => (),
+            }
+        }
+    }
+
+    None
+}
+
+// An encoder is catastrophic when a zero-output cycle carries a nonzero input, so that an input
+// sequence of infinite weight produces a codeword of finite weight. A branch lies on a cycle of the
+// zero-output subgraph exactly when both of its states are in the same strongly connected component.
+pub fn is_catastrophic(trellis: &Trellis) -> bool {
+
+    let num_states = trellis.num_states;
+
+    let mut successors: Vec<Vec<usize>> = vec![vec![]; num_states];
+    let mut predecessors: Vec<Vec<usize>> = vec![vec![]; num_states];
+    for (state, state_successors) in successors.iter_mut().enumerate() {
+        for input in 0..trellis.num_input_symbols {
+            if is_zero_output_branch(trellis, state, input) {
+                let next_state = *trellis.next_states.index(state, input);
+                state_successors.push(next_state);
+                predecessors[next_state].push(state);
+            }
+        }
+    }
+
+    // Kosaraju: order the states by DFS finishing time, then collect components on the reversed graph
+    let mut visited: Vec<bool> = vec![false; num_states];
+    let mut finish_order: Vec<usize> = Vec::with_capacity(num_states);
+    for root in 0..num_states {
+        if visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        let mut stack: Vec<(usize, usize)> = vec![(root, 0)];
+        while let Some(top) = stack.last_mut() {
+            let (state, index) = *top;
+            if index == successors[state].len() {
+                finish_order.push(state);
+                stack.pop();
+                continue;
+            }
+            top.1 += 1;
+            let next_state = successors[state][index];
+            if !visited[next_state] {
+                visited[next_state] = true;
+                stack.push((next_state, 0));
+            }
+        }
+    }
+
+    let unassigned = usize::MAX;
+    let mut component: Vec<usize> = vec![unassigned; num_states];
+    for &root in finish_order.iter().rev() {
+        if component[root] != unassigned {
+            continue;
+        }
+        component[root] = root;
+        let mut stack: Vec<usize> = vec![root];
+        while let Some(state) = stack.pop() {
+            for &pre_state in &predecessors[state] {
+                if component[pre_state] == unassigned {
+                    component[pre_state] = root;
+                    stack.push(pre_state);
+                }
+            }
+        }
+    }
+
+    (0..num_states).any(|state| {
+        (1..trellis.num_input_symbols).any(|input| {
+            is_zero_output_branch(trellis, state, input)
+                && component[state] == component[*trellis.next_states.index(state, input)]
+        })
+    })
+}
+
+// Zero-output branches, leaving out the all-zero self-loop of state 0
+fn is_zero_output_branch(trellis: &Trellis, state: usize, input: usize) -> bool {
+    *trellis.outputs.index(state, input) == 0 && !(state == 0 && input == 0)
+}
+
+// Massey-Sain condition for a rate-1/n encoder given in the octal convention of
+// generate_rate_1_n_trellis: the encoder G(D) = [g_1(D), ..., g_n(D)] / f(D) is catastrophic
+// if and only if gcd(g_1, ..., g_n), once the factors it shares with f(D) are cancelled, is not
+// a monomial D^l. It agrees with is_catastrophic on the trellis of the same encoder.
+pub fn is_catastrophic_rate_1_n(constraint_length: u16, generators: &[u64], feedback: Option<u64>) -> Result<bool, TrellisError> {
+
+    let mut common = p64(0);
+    for &octal in generators {
+        let generator = reverse_bits(octal_to_binary(octal, constraint_length)?, constraint_length);
+        common = gf2_gcd(common, p64(generator));
+    }
+
+    if let Some(octal) = feedback {
+        let feedback = reverse_bits(octal_to_binary(octal, constraint_length)?, constraint_length);
+        if u64::from(common) != 0 {
+            common = common.naive_div(gf2_gcd(common, p64(feedback)));
+        }
+    }
+
+    // D^l is the only nonzero polynomial left with degree 0 once its factors of D are divided out
+    let common = u64::from(common);
+    Ok(common == 0 || gf2_degree(p64(common >> common.trailing_zeros())) != 0)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::catastrophic::{find_zero_output_cycle, is_catastrophic, is_catastrophic_rate_1_n};
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+
+    #[test]
+    fn test_catastrophic_feedforward() {
+        // g_1 = 1 + D and g_2 = 1 + D^2 share the factor 1 + D
+        let trellis = generate_rate_1_n_trellis(3, &[6, 5], None).unwrap();
+        assert!(is_catastrophic_rate_1_n(3, &[6, 5], None).unwrap());
+        assert!(is_catastrophic(&trellis));
+
+        let cycle = find_zero_output_cycle(&trellis).unwrap();
+        let mut state = cycle[0].0;
+        for (cycle_state, input) in &cycle {
+            assert_eq!(state, *cycle_state);
+            assert_eq!(*trellis.outputs.index(state, *input), 0);
+            state = *trellis.next_states.index(state, *input);
+        }
+        assert_eq!(state, cycle[0].0);
+    }
+
+    #[test]
+    fn test_non_catastrophic_encoders() {
+        assert!(!is_catastrophic_rate_1_n(7, &[133, 171], None).unwrap());
+        assert!(!is_catastrophic(&generate_rate_1_n_trellis(7, &[133, 171], None).unwrap()));
+        assert!(!is_catastrophic_rate_1_n(4, &[13, 15], Some(13)).unwrap());
+        assert!(!is_catastrophic(&generate_rate_1_n_trellis(4, &[13, 15], Some(13)).unwrap()));
+        assert!(!is_catastrophic(&generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap()));
+    }
+
+    #[test]
+    fn test_common_factor_cancelled_by_feedback() {
+        // (1 + D) divides both generators and the feedback, so G(D) = [1, 1 + D] is not catastrophic,
+        // but its realization with two memory elements has an unobservable zero-output cycle
+        assert!(!is_catastrophic_rate_1_n(3, &[6, 5], Some(6)).unwrap());
+        let trellis = generate_rate_1_n_trellis(3, &[6, 5], Some(6)).unwrap();
+        assert!(!is_catastrophic(&trellis));
+        assert_eq!(find_zero_output_cycle(&trellis), Some(vec![(3, 0)]));
+    }
+}
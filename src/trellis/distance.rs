@@ -0,0 +1,190 @@
+use crate::trellis::trellis::Trellis;
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+// Free distance of a code and the number of error events (paths that leave state 0 with a
+// nonzero input and first return to it) of that weight. The multiplicity is None when it is
+// infinite, which happens when a zero-output cycle lies on a minimum-weight error event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FreeDistance {
+    pub free_distance: u32,
+    pub multiplicity: Option<u64>,
+}
+
+// Computes d_free with Dijkstra's algorithm over the states, then counts the error events of
+// weight d_free by propagating path counts one trellis section at a time. Only next_states and
+// outputs are used, so this is a cheap check compared to the full IEE enumeration.
+pub fn find_free_distance(trellis: &Trellis) -> FreeDistance {
+
+    let num_states = trellis.num_states;
+    let num_input_symbols = trellis.num_input_symbols;
+    let branch_weight = |state: usize, input: usize| trellis.outputs.index(state, input).count_ones();
+
+    // minimum weight needed to get from each state back to state 0, on the reversed trellis
+    let mut pre_branches: Vec<Vec<(usize, u32)>> = vec![vec![]; num_states];
+    for state in 0..num_states {
+        for input in 0..num_input_symbols {
+            pre_branches[*trellis.next_states.index(state, input)].push((state, branch_weight(state, input)));
+        }
+    }
+    let mut weight_to_go: Vec<u32> = vec![u32::MAX; num_states];
+    let mut heap: BinaryHeap<Reverse<(u32, usize)>> = BinaryHeap::new();
+    weight_to_go[0] = 0;
+    heap.push(Reverse((0, 0)));
+    while let Some(Reverse((weight, state))) = heap.pop() {
+        if weight > weight_to_go[state] {
+            continue;
+        }
+        for &(pre_state, pre_weight) in &pre_branches[state] {
+            if pre_state != 0 && weight + pre_weight < weight_to_go[pre_state] {
+                weight_to_go[pre_state] = weight + pre_weight;
+                heap.push(Reverse((weight + pre_weight, pre_state)));
+            }
+        }
+    }
+
+    // an error event is a nonzero input out of state 0 followed by the cheapest way back
+    let free_distance = (1..num_input_symbols)
+        .map(|input| {
+            let next_state = *trellis.next_states.index(0, input);
+            branch_weight(0, input).saturating_add(weight_to_go[next_state])
+        })
+        .min()
+        .expect("the trellis has no nonzero input");
+
+    // counts[state][weight] holds the number of unmerged paths in state with that accumulated weight
+    let mut multiplicity: u64 = 0;
+    let mut counts: Vec<Vec<u64>> = vec![vec![0; free_distance as usize + 1]; num_states];
+    for input in 1..num_input_symbols {
+        let next_state = *trellis.next_states.index(0, input);
+        let weight = branch_weight(0, input);
+        if weight.saturating_add(weight_to_go[next_state]) != free_distance {
+            continue;
+        }
+        if next_state == 0 {
+            multiplicity += 1;
+        } else {
+            counts[next_state][weight as usize] += 1;
+        }
+    }
+
+    // without zero-output cycles every path of weight <= d_free remerges within this many sections
+    let max_sections = num_states * (free_distance as usize + 1) + 1;
+    for _ in 0..max_sections {
+        if counts.iter().all(|row| row.iter().all(|&count| count == 0)) {
+            return FreeDistance { free_distance, multiplicity: Some(multiplicity) };
+        }
+
+        let mut next_counts: Vec<Vec<u64>> = vec![vec![0; free_distance as usize + 1]; num_states];
+        for (state, row) in counts.iter().enumerate() {
+            for (weight, &count) in row.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                for input in 0..num_input_symbols {
+                    let next_state = *trellis.next_states.index(state, input);
+                    let next_weight = weight as u32 + branch_weight(state, input);
+                    if next_weight.saturating_add(weight_to_go[next_state]) > free_distance {
+                        continue;
+                    }
+                    if next_state == 0 {
+                        multiplicity += count;
+                    } else {
+                        next_counts[next_state][next_weight as usize] += count;
+                    }
+                }
+            }
+        }
+        counts = next_counts;
+    }
+
+    FreeDistance { free_distance, multiplicity: None }
+}
+
+// Column distance profile d_0, d_1, ..., d_{num_columns - 1}: d_j is the minimum output weight of
+// the first j + 1 trellis sections over all paths whose first input is nonzero. The paths may pass
+// through state 0, so d_j never decreases and reaches d_free for a non-catastrophic code.
+pub fn column_distance_profile(trellis: &Trellis, num_columns: usize) -> Vec<u32> {
+
+    let num_states = trellis.num_states;
+    let mut profile: Vec<u32> = Vec::with_capacity(num_columns);
+    if num_columns == 0 {
+        return profile;
+    }
+
+    let mut weights: Vec<u32> = vec![u32::MAX; num_states];
+    for input in 1..trellis.num_input_symbols {
+        let next_state = *trellis.next_states.index(0, input);
+        let weight = trellis.outputs.index(0, input).count_ones();
+        weights[next_state] = weights[next_state].min(weight);
+    }
+    profile.push(*weights.iter().min().unwrap());
+
+    for _ in 1..num_columns {
+        let mut next_weights: Vec<u32> = vec![u32::MAX; num_states];
+        for (state, &weight) in weights.iter().enumerate() {
+            if weight == u32::MAX {
+                continue;
+            }
+            for input in 0..trellis.num_input_symbols {
+                let next_state = *trellis.next_states.index(state, input);
+                let next_weight = weight + trellis.outputs.index(state, input).count_ones();
+                next_weights[next_state] = next_weights[next_state].min(next_weight);
+            }
+        }
+        weights = next_weights;
+        profile.push(*weights.iter().min().unwrap());
+    }
+
+    profile
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::distance::{find_free_distance, column_distance_profile, FreeDistance};
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_free_distance_of_standard_codes() {
+        // (7, 5): d_free = 5 with a single error event, (171, 133): d_free = 10 with 11 error events
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        assert_eq!(find_free_distance(&trellis), FreeDistance { free_distance: 5, multiplicity: Some(1) });
+
+        let trellis = poly2trellis(&[7], &Vec2d::new(vec![171, 133], 1, 2)).unwrap();
+        assert_eq!(find_free_distance(&trellis), FreeDistance { free_distance: 10, multiplicity: Some(11) });
+    }
+
+    #[test]
+    fn test_feedback_does_not_change_free_distance() {
+        // the recursive systematic version of (15, 13) generates the same code as the feedforward one
+        let feedforward = generate_rate_1_n_trellis(4, &[15, 13], None).unwrap();
+        let recursive = generate_rate_1_n_trellis(4, &[15, 13], Some(15)).unwrap();
+        assert_eq!(find_free_distance(&feedforward).free_distance, 6);
+        assert_eq!(find_free_distance(&recursive).free_distance, 6);
+    }
+
+    #[test]
+    fn test_column_distance_profile() {
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        assert_eq!(column_distance_profile(&trellis, 8), vec![2, 3, 3, 4, 4, 5, 5, 5]);
+
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        let free_distance = find_free_distance(&trellis).free_distance;
+        let profile = column_distance_profile(&trellis, 20);
+        assert!(profile.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(*profile.last().unwrap(), free_distance);
+    }
+
+    #[test]
+    fn test_infinite_multiplicity() {
+        // G(D) = [1 + D, 1 + D] is catastrophic: input 1, 1, ..., 1 has weight 4 however long it is
+        let trellis = generate_rate_1_n_trellis(2, &[3, 3], None).unwrap();
+        assert_eq!(find_free_distance(&trellis), FreeDistance { free_distance: 4, multiplicity: None });
+    }
+}
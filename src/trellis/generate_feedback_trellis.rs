@@ -1,59 +1,71 @@
+use crate::trellis::trellis::{Trellis, TrellisError, MAX_TABLE_BITS, octal_to_binary, reverse_bits};
 
-use crate::trellis::trellis::Trellis;
+use crate::trellis::terminations::{find_shortest_terminations, find_termination_outputs};
 
-use convert_base::Convert;
-use ::gf256::p16;
-use ::bit_reverse::LookupReverse;
-use ::queues::{IsQueue, Queue, queue};
+use ::gf256::p64;
 
-use crate::vec2d::{
-    Vec2d,
-    fill_p16,
-    fill,
-};
+use crate::vec2d::Vec2d;
 
-// Creates a trellis and termination sequence for a nonsystematic high-rate ( R = (n-1) / n ) feedback encoder
-pub fn generate_feedback_trellis(v: u16, numerators: [u16; 3], octal_denominator: u16) -> Trellis {
+// Widest polynomial the GF(2) arithmetic below can hold
+const MAX_POLY_BITS: u16 = 64;
 
-    const TWO: usize = 2;
-    let k: u16 = numerators.len() as u16;
-    let n: u16 = k + 1;
+// Creates a trellis and termination sequence for a nonsystematic high-rate ( R = k / (k+1) ) feedback encoder.
+// The number of input rails k is the number of numerators, so rate-1/2, 2/3, 3/4, ... encoders are all supported.
+// All polynomial arithmetic is done on 64-bit GF(2) polynomials, so v may be as large as the state table allows.
+pub fn generate_feedback_trellis(v: u16, numerators: &[u64], octal_denominator: u64) -> Result<Trellis, TrellisError> {
 
-    let _base: convert_base::Convert = Convert::new(8, 2);
+    let k: usize = numerators.len();
+    let n: usize = k + 1;
 
-    let num_input_symbols: usize = TWO.pow(k.into());
-    let num_output_symbols: usize = TWO.pow(n.into());
-    let num_states: usize = TWO.pow((v - 1).into());
+    // the output symbol holds k+1 bits and the state table holds 2^(v-1) * 2^k entries
+    let max_k = (MAX_POLY_BITS - 1).min(MAX_TABLE_BITS - 1) as usize;
+    if k == 0 || k > max_k {
+        return Err(TrellisError::UnsupportedInputRails { k, max_k });
+    }
+    let max_v = (MAX_TABLE_BITS + 1 - k as u16).min(MAX_POLY_BITS);
+    if v < 2 || v > max_v {
+        return Err(TrellisError::UnsupportedMemory { v, max_v });
+    }
 
-    let mut next_states: Vec2d<p16> = Vec2d::new(fill_p16(0, num_states * num_input_symbols), num_states, num_input_symbols);
-    let mut outputs: Vec2d<p16> = Vec2d::new(fill_p16(0, num_states * num_input_symbols), num_states, num_input_symbols);
+    let num_input_symbols: usize = 1 << k;
+    let num_output_symbols: usize = 1 << n;
+    let num_states: usize = 1 << (v - 1);
 
-    let decimal_denominator: u16 = u16::from_str_radix(&octal_denominator.to_string(), 8).unwrap();
+    let mut next_states: Vec2d<usize> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+    let mut outputs: Vec2d<u64> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+
+    let decimal_denominator: u64 = octal_to_binary(octal_denominator, v)?;
+    if decimal_denominator & 1 == 0 {
+        return Err(TrellisError::DenominatorNotDelayFree(octal_denominator));
+    }
 
     // Reverse the vector and convert the octal numbers into their binary representation
     // (They will still look like base-10, but their underlying binary digits will align with the original octal representation)
-    let revved_nums: Vec<p16> = numerators.iter().map(|n| p16(u16::from_str_radix(&n.to_string(), 8).unwrap())).rev().collect::<Vec<p16>>();
+    let revved_nums: Vec<p64> = numerators.iter()
+        .map(|n| octal_to_binary(*n, v).map(p64))
+        .rev()
+        .collect::<Result<Vec<p64>, TrellisError>>()?;
+    let revved_denom = p64(reverse_bits(decimal_denominator, v));
 
     for current_state in 0..num_states {
         for input_symbol in 0..num_input_symbols {
 
-            let input_symbol_revved: u16 = ((input_symbol as u16).swap_bits() >> (16 - k)) as u16;
-            let mut total_numerator: p16 = p16(0);
+            let input_symbol_revved: u64 = reverse_bits(input_symbol as u64, k as u16);
+            let mut total_numerator: p64 = p64(0);
 
-            for nth_digit in 0..k {
-                let nth_bit: p16 = p16(((input_symbol_revved >> nth_digit) & 1) as u16);
-                total_numerator += revved_nums[nth_digit as usize] * nth_bit;
+            for (nth_digit, &revved_num) in revved_nums.iter().enumerate() {
+                let nth_bit: p64 = p64((input_symbol_revved >> nth_digit) & 1);
+                total_numerator += revved_num * nth_bit;
             }
-            total_numerator += p16(current_state as u16);
+            total_numerator += p64(current_state as u64);
 
             // get remainder
-            let revved_total_num = p16((u16::from(total_numerator)).swap_bits() >> (16 - v));
-            let revved_denom = p16(decimal_denominator.swap_bits() >> (16 - v));
+            let revved_total_num = p64(reverse_bits(u64::from(total_numerator), v));
             let quotient = revved_total_num.naive_div(revved_denom);
             let remainder = revved_total_num.naive_rem(revved_denom);
-            let revved_remainder: p16 = p16((u16::from(remainder)).swap_bits() >> (16 - v + 1));
-            *next_states.index_mut(current_state, input_symbol) = revved_remainder;
-            *outputs.index_mut(current_state, input_symbol) = p16(input_symbol as u16) + (quotient << k); // add quotient bit after the other bits
+            let revved_remainder: u64 = reverse_bits(u64::from(remainder), v - 1);
+            *next_states.index_mut(current_state, input_symbol) = revved_remainder as usize;
+            *outputs.index_mut(current_state, input_symbol) = input_symbol as u64 + (u64::from(quotient) << k); // add quotient bit after the other bits
         }
     }
 
@@ -62,69 +74,99 @@ pub fn generate_feedback_trellis(v: u16, numerators: [u16; 3], octal_denominator
 
     
     // Find shortest termination sequence
+    let num_transitions: usize = (usize::from(v) - 1).div_ceil(k);
+    let terminations = find_shortest_terminations(&next_states, num_transitions)?;
 
-    let mut queue: Queue<u16> = queue![0];
-    let mut visited: Vec<u16> = fill(0, num_states);
-    let mut tree: Vec<u16> = fill(0, num_states);
-    visited[0] = 1;
-
-    while queue.size() != 0 {
-        let target_state_option = queue.remove();
-        match target_state_option {
-            Ok(target_state) => {
-                for pre_state in 0..num_states {
-                    if next_states.row(pre_state).contains(&p16(target_state)) && visited[pre_state] == 0 {
-                        visited[pre_state] = 1;
-                        let res = queue.add(pre_state as u16);
-                        match res {
-                            Ok(correct) => correct,
-                            Err(error) => panic!("Problem adding to queue: {:?}", error),
-                        };
-                        tree[pre_state] = target_state;
-                    }
-                }
-            },
-            Err(_error) => break,
-        }
-    }
+    //println!("{:?}", terminations);
 
+    let termination_outputs = find_termination_outputs(&next_states, &outputs, &terminations);
 
-    let mut terminations: Vec<Vec<u16>> = vec![vec![]; num_states];
+    let trellis: Trellis = Trellis {
+        num_input_rails: k,
+        num_input_symbols,
+        num_output_symbols,
+        num_states,
+        next_states,
+        outputs,
+        terminations,
+        termination_outputs,
+        termination_length: num_transitions,
+    };
 
-    terminations[0].push(0);
+    Ok(trellis)
+}
 
-    for current_state in 1..num_states {
-        let mut current_tmp = current_state;
-        while current_tmp != 0 {
-            let father_state = tree[current_tmp];
-            let index_option = next_states.row(current_tmp).iter().position(|&x| x == p16(father_state));
-            match index_option {
-                Some(index) => terminations[current_state].push(index as u16),
-                None => (),
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::trellis::TrellisError;
+
+    #[test]
+    fn test_rate_3_4_dimensions() {
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        assert_eq!(trellis.num_input_rails, 3);
+        assert_eq!(trellis.num_input_symbols, 8);
+        assert_eq!(trellis.num_output_symbols, 16);
+        assert_eq!(trellis.num_states, 64);
+        assert_eq!(trellis.termination_length, 2);
+    }
+
+    #[test]
+    fn test_rate_1_2_dimensions() {
+        let trellis = generate_feedback_trellis(4, &[13], 15).unwrap();
+        assert_eq!(trellis.num_input_rails, 1);
+        assert_eq!(trellis.num_input_symbols, 2);
+        assert_eq!(trellis.num_output_symbols, 4);
+        assert_eq!(trellis.num_states, 8);
+        assert_eq!(trellis.termination_length, 3);
+    }
+
+    #[test]
+    fn test_terminations_reach_zero_state() {
+        for (v, numerators, denominator) in [(4, vec![13], 15), (4, vec![15, 17], 13), (7, vec![107, 135, 133], 141)] {
+            let trellis = generate_feedback_trellis(v, &numerators, denominator).unwrap();
+            for start_state in 0..trellis.num_states {
+                let termination = &trellis.terminations[start_state];
+                assert_eq!(termination.len(), trellis.termination_length);
+                let mut state = start_state;
+                for input in termination {
+                    state = *trellis.next_states.index(state, *input);
+                }
+                assert_eq!(state, 0);
             }
-            current_tmp = usize::from(father_state);
         }
     }
 
-    let num_transitions: usize = usize::from((v-1 + k - 1) / k);
-    for current_state in 0..num_states {
-        let length = terminations[current_state].len();
-        if length < num_transitions {
-            let mut tmp = vec![0_u16; num_transitions - length];
-            terminations[current_state].append(&mut tmp);
+    #[test]
+    fn test_more_than_16_memory_elements() {
+        // numerators and denominator wider than a u16 once read as octal
+        let trellis = generate_feedback_trellis(18, &[234567, 312345], 400003).unwrap();
+        assert_eq!(trellis.num_states, 1 << 17);
+        assert_eq!(trellis.termination_length, 9);
+        for start_state in [1, 12345, (1 << 17) - 1] {
+            let mut state = start_state;
+            for input in &trellis.terminations[start_state] {
+                state = *trellis.next_states.index(state, *input);
+            }
+            assert_eq!(state, 0);
         }
     }
 
-    //println!("{:?}", terminations);
-
-    let trellis: Trellis = Trellis {
-        num_input_symbols: num_input_symbols,
-        num_output_symbols: num_output_symbols,
-        num_states: num_states,
-        next_states: next_states,
-        outputs,
-        terminations,
-    };
+    #[test]
+    fn test_unrepresentable_codes() {
+        assert_eq!(generate_feedback_trellis(4, &[19], 15).err(), Some(TrellisError::InvalidOctal(19)));
+        assert_eq!(generate_feedback_trellis(4, &[13], 35).err(), Some(TrellisError::PolynomialTooWide { octal: 35, v: 4 }));
+        assert_eq!(generate_feedback_trellis(4, &[13], 16).err(), Some(TrellisError::DenominatorNotDelayFree(16)));
+        assert_eq!(generate_feedback_trellis(4, &[], 15).err(), Some(TrellisError::UnsupportedInputRails { k: 0, max_k: 25 }));
+        assert!(matches!(generate_feedback_trellis(70, &[13], 15), Err(TrellisError::UnsupportedMemory { v: 70, .. })));
+    }
 
-    trellis
-}
\ No newline at end of file
+    #[test]
+    fn test_state_table_too_large() {
+        // 2^39 * 2 table entries would not fit in memory
+        assert_eq!(generate_feedback_trellis(40, &[13], 15).err(), Some(TrellisError::UnsupportedMemory { v: 40, max_v: 26 }));
+        assert_eq!(generate_feedback_trellis(26, &[13, 13], 15).err(), Some(TrellisError::UnsupportedMemory { v: 26, max_v: 25 }));
+        assert_eq!(generate_feedback_trellis(4, &[13; 26], 15).err(), Some(TrellisError::UnsupportedInputRails { k: 26, max_k: 25 }));
+    }
+}
@@ -0,0 +1,157 @@
+use crate::trellis::terminations::find_termination_outputs;
+use crate::trellis::trellis::{Trellis, TrellisError, MAX_TABLE_BITS, octal_to_binary};
+
+use crate::vec2d::Vec2d;
+
+// Creates a trellis and termination sequence for a rate-1/n encoder with a single shift register of
+// constraint_length taps, following MATLAB's poly2trellis(K, [g_1 ... g_n], feedback):
+//   1) generators are the n octal output connections; the MSB of each K-bit generator taps the current
+//      register input and the LSB the oldest register bit. The first generator is the MSB of the output symbol
+//   2) feedback is None for a feedforward encoder, or the octal feedback connection of a recursive encoder.
+//      A generator equal to the feedback connection outputs the input bit, which makes the encoder systematic
+//   3) the state is the register contents with the most recent bit as the MSB
+//
+// Terminations drive the register with zeros for constraint_length - 1 transitions: a feedforward
+// encoder is fed zero inputs, while a recursive encoder is fed the feedback sum of its register so
+// that the bit entering the register is zero.
+pub fn generate_rate_1_n_trellis(constraint_length: u16, generators: &[u64], feedback: Option<u64>) -> Result<Trellis, TrellisError> {
+
+    let n: usize = generators.len();
+    let max_n = u64::BITS as usize;
+    if n == 0 || n > max_n {
+        return Err(TrellisError::UnsupportedOutputs { n, max_n });
+    }
+    // the state table holds 2^(K-1) * 2 entries
+    let max_v = MAX_TABLE_BITS;
+    if constraint_length == 0 || constraint_length > max_v {
+        return Err(TrellisError::UnsupportedMemory { v: constraint_length, max_v });
+    }
+
+    let memory: usize = usize::from(constraint_length) - 1;
+    let generators: Vec<u64> = generators.iter()
+        .map(|&octal| octal_to_binary(octal, constraint_length))
+        .collect::<Result<Vec<u64>, TrellisError>>()?;
+
+    // the feedback taps on the register, without the tap on the register input
+    let feedback_taps: u64 = match feedback {
+        Some(octal) => {
+            let binary = octal_to_binary(octal, constraint_length)?;
+            if (binary >> memory) & 1 == 0 {
+                return Err(TrellisError::DenominatorNotDelayFree(octal));
+            }
+            binary & ((1 << memory) - 1)
+        },
+        None => 0,
+    };
+
+    let num_input_symbols: usize = 2;
+    let num_output_symbols: usize = 1 << n;
+    let num_states: usize = 1 << memory;
+
+    let mut next_states: Vec2d<usize> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+    let mut outputs: Vec2d<u64> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+
+    for current_state in 0..num_states {
+        let feedback_bit = u64::from((current_state as u64 & feedback_taps).count_ones() % 2);
+        for input_symbol in 0..num_input_symbols {
+            let register_input = input_symbol as u64 ^ feedback_bit;
+
+            // the register input followed by the register contents, newest to oldest
+            let window: u64 = (register_input << memory) | current_state as u64;
+            let mut output: u64 = 0;
+            for (j, generator) in generators.iter().enumerate() {
+                let bit = u64::from((window & generator).count_ones() % 2);
+                output |= bit << (n - 1 - j);
+            }
+
+            *next_states.index_mut(current_state, input_symbol) = (window >> 1) as usize;
+            *outputs.index_mut(current_state, input_symbol) = output;
+        }
+    }
+
+    // Flush the register: the input that makes the register input zero is the feedback bit itself
+    let terminations: Vec<Vec<usize>> = (0..num_states).map(|start_state| {
+        let mut state = start_state;
+        (0..memory).map(|_| {
+            let input = ((state as u64 & feedback_taps).count_ones() % 2) as usize;
+            state = *next_states.index(state, input);
+            input
+        }).collect()
+    }).collect();
+
+    let termination_outputs = find_termination_outputs(&next_states, &outputs, &terminations);
+
+    let trellis: Trellis = Trellis {
+        num_input_rails: 1,
+        num_input_symbols,
+        num_output_symbols,
+        num_states,
+        next_states,
+        outputs,
+        terminations,
+        termination_outputs,
+        termination_length: memory,
+    };
+
+    Ok(trellis)
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::trellis::{Trellis, TrellisError};
+    use crate::vec2d::Vec2d;
+
+    fn assert_terminations_reach_zero(trellis: &Trellis) {
+        for start_state in 0..trellis.num_states {
+            let mut state = start_state;
+            for input in &trellis.terminations[start_state] {
+                state = *trellis.next_states.index(state, *input);
+            }
+            assert_eq!(state, 0);
+        }
+    }
+
+    #[test]
+    fn test_feedforward_matches_poly2trellis() {
+        let trellis = generate_rate_1_n_trellis(7, &[133, 171], None).unwrap();
+        let reference = poly2trellis(&[7], &Vec2d::new(vec![133, 171], 1, 2)).unwrap();
+        assert_eq!(trellis.num_output_symbols, 4);
+        assert_eq!(trellis.num_states, 64);
+        assert_eq!(trellis.next_states.vec, reference.next_states.vec);
+        assert_eq!(trellis.outputs.vec, reference.outputs.vec);
+        assert_eq!(trellis.terminations, vec![vec![0; 6]; 64]);
+        assert_terminations_reach_zero(&trellis);
+    }
+
+    #[test]
+    fn test_systematic_recursive() {
+        // poly2trellis(4, [13 15 17], 13)
+        let trellis = generate_rate_1_n_trellis(4, &[13, 15, 17], Some(13)).unwrap();
+        assert_eq!(trellis.num_output_symbols, 8);
+        assert_eq!(trellis.termination_length, 3);
+        for state in 0..trellis.num_states {
+            for input in 0..2 {
+                // the first output is the systematic bit
+                assert_eq!(*trellis.outputs.index(state, input) >> 2, input as u64);
+            }
+        }
+        assert_ne!(trellis.terminations[1], vec![0; 3]);
+        assert_terminations_reach_zero(&trellis);
+    }
+
+    #[test]
+    fn test_invalid_feedback() {
+        assert_eq!(generate_rate_1_n_trellis(4, &[13, 15], Some(3)).err(), Some(TrellisError::DenominatorNotDelayFree(3)));
+        assert_eq!(generate_rate_1_n_trellis(3, &[13, 15], None).err(), Some(TrellisError::PolynomialTooWide { octal: 13, v: 3 }));
+    }
+
+    #[test]
+    fn test_state_table_too_large() {
+        assert_eq!(generate_rate_1_n_trellis(40, &[1, 1], None).err(), Some(TrellisError::UnsupportedMemory { v: 40, max_v: 26 }));
+        assert!(generate_rate_1_n_trellis(0, &[1, 1], None).is_err());
+    }
+}
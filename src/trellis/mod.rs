@@ -1,3 +1,10 @@
 pub mod poly2trellis;
+pub mod rational_generator_matrix;
 pub mod generate_feedback_trellis;
-pub mod trellis;
\ No newline at end of file
+pub mod generate_rate_1_n_trellis;
+pub mod catastrophic;
+pub mod distance;
+pub mod transfer_function;
+pub mod puncturing;
+pub mod terminations;
+pub mod trellis;
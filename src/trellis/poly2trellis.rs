@@ -1,75 +1,235 @@
-
-
-
-
-
-// pub fn poly2trellis_bruteforce(constraint_length: Vec<u16>, code_generator: Vec2d<u16>) -> Trellis {
-
-//     const two: usize = 2;
-//     let numOutputSymbols: usize = two.pow((code_generator.col) as u32) as usize;
-//     let cols: usize = two.pow(constraint_length.len() as u32) as usize;
-//     let rows: usize = two.pow((constraint_length.iter().map(|n| n - 1).sum::<u16>()) as u32) as usize;
-
-//     let mut states: Vec2d<u16> = Vec2d::new(Fill(0, rows * cols), rows, cols);
-
-//     for n in 0..states.row {
-//         for m in 0..states.col {
-//             // shift bits corresponding to row position right by 1
-//             // take a 1 and shift to right by constraint_length - 1
-//             // take shifted bits OR 
-
-//             // shift states for each set of memory bits
-//             *states.index_mut(n, m) = Shift_registers(n, m, &constraint_length);
-//             //new_state = ((n >> 1) | (m << (constraint_length[0] - 2))) as u16;
-//         }
-//     }
-
-//     let mut outputs: Vec2d<u16> = Vec2d::new(Fill(0, rows * cols), rows, cols);
-
-//     for n in 0..outputs.row {
-//         for m in 0..outputs.col {
-
-//             let mut output: u32 = 0;
-//             // need to modify this so that we grab the 
-//             for (i, node) in code_generator.vec.iter().rev().enumerate() {
-//                 // crazy ass function
-//                 // basically you are calculating the output of each node for a given state
-//                 // we start backward because the last node's output will be the LSB
-//                 // each node is acting as a bit in an output sum
-//                 output += ((node & (n + (m << (constraint_length[0] - 1))) as u16).count_ones() % 2) << i;
-//             }
-            
-//             *outputs.index_mut(n, m) = output as u16;
-//         }
-//     }
-
-    // let trellis: Trellis = Trellis {
-    //     numInputSymbols: cols,
-    //     numOutputSymbols: numOutputSymbols, // will have to change this to find the n*m size for a 2d array at some point, but for now (since we only have 1 input) this will work
-    //     numStates: rows,
-    //     nextStates: states,
-    //     outputs: outputs,
-    // };
-
-//     trellis
-// }
-
-// fn Shift_registers(n: usize, m: usize, constraint_lengths: &Vec<u16>) -> u16 {
-//     let mut new_state: u16 = 0;
-//     let mut bits_to_skip = 0;
-//     for (i, constraint_len) in constraint_lengths.iter().enumerate() {
-//         // we want to extract the bits corresponding to each constraint length (constraint_length goes in ascending order from LSB to MSB)
-
-//         // finds the value of the column's bit at index: constraint_lengths.len() - 1 - i
-//         let mut input_bit = (m >> constraint_lengths.len() - 1 - i) & 1;
-//         //let mut input_bit = Get_bit_at_idx(m, constraint_lengths.len() - i - 1);
-//         let mut new_bits_for_constraint: u16 = (((n >> bits_to_skip) & !(!0 << constraint_len)) >> 1 | (input_bit << (constraint_len - 2))) as u16;
-//         if n < 3 {
-//             println!("index for input_bit for row {} col {}: {}, input bit: {}", n, m, constraint_lengths.len() - i - 1, input_bit);
-//         }
-//         new_state |= (new_bits_for_constraint << bits_to_skip);
-//         bits_to_skip += constraint_len - 1;
-//     }
-
-//     new_state
-// }
\ No newline at end of file
+use crate::trellis::trellis::{Trellis, TrellisError, MAX_TABLE_BITS, octal_to_binary, binary_to_octal};
+use crate::trellis::terminations::{find_shortest_terminations, find_termination_outputs};
+
+use crate::vec2d::Vec2d;
+
+// Creates a trellis and termination sequence for a feedforward (non-recursive) k-by-n convolutional
+// encoder, following the conventions of MATLAB's poly2trellis(ConstraintLength, CodeGenerator):
+//   1) constraint_lengths[i] is the number of taps (memory + 1) of the shift register of input rail i
+//   2) code_generator is a k-by-n matrix of octal generators, where entry (i, j) connects input rail i
+//      to output j. The MSB of each K_i-bit generator taps the current input, the LSB the oldest input
+//   3) the first input rail is the MSB of the input symbol and the first output is the MSB of the output symbol
+//   4) the state concatenates the shift registers with rail 1 in the MSBs, the most recent input bit
+//      being the MSB of each register
+// The termination length is the longest register, max(K_i) - 1.
+pub fn poly2trellis(constraint_lengths: &[u16], code_generator: &Vec2d<u64>) -> Result<Trellis, TrellisError> {
+
+    let generators = generators_to_binary(constraint_lengths, code_generator)?;
+
+    // a feedforward register only taps its own input
+    let feedback: Vec<u64> = constraint_lengths.iter().map(|&length| 1 << (length - 1)).collect();
+
+    shift_register_trellis(constraint_lengths, &generators, &feedback)
+}
+
+// Same as poly2trellis, with the feedback connection of every rail as in MATLAB's
+// poly2trellis(ConstraintLength, CodeGenerator, FeedbackConnection). feedback_connections[i] is the
+// octal K_i-bit feedback of rail i, whose MSB (the tap on the register input) must be set.
+// A generator equal to the rail's feedback connection outputs that rail's input bit.
+pub fn poly2trellis_with_feedback(
+    constraint_lengths: &[u16], code_generator: &Vec2d<u64>, feedback_connections: &[u64]
+) -> Result<Trellis, TrellisError> {
+
+    if feedback_connections.len() != constraint_lengths.len() {
+        return Err(TrellisError::GeneratorShapeMismatch {
+            constraint_lengths: constraint_lengths.len(), generator_rows: feedback_connections.len() });
+    }
+    let generators = generators_to_binary(constraint_lengths, code_generator)?;
+
+    let feedback: Vec<u64> = feedback_connections.iter().zip(constraint_lengths)
+        .map(|(&octal, &length)| {
+            let taps = octal_to_binary(octal, length)?;
+            if (taps >> (length - 1)) & 1 == 0 {
+                return Err(TrellisError::DenominatorNotDelayFree(octal));
+            }
+            Ok(taps)
+        })
+        .collect::<Result<Vec<u64>, TrellisError>>()?;
+
+    shift_register_trellis(constraint_lengths, &generators, &feedback)
+}
+
+// Builds the trellis of k shift registers from binary tap patterns (MSB of each K_i-bit pattern on the
+// register input). generators is the k-by-n matrix of output taps and feedback the K_i-bit feedback
+// taps of every rail, with the register input of rail i being u_i + the feedback taps on the register.
+// The MSB of every feedback pattern must be set, or DenominatorNotDelayFree is returned.
+pub fn shift_register_trellis(constraint_lengths: &[u16], generators: &Vec2d<u64>, feedback: &[u64]) -> Result<Trellis, TrellisError> {
+
+    let k: usize = constraint_lengths.len();
+    let n: usize = generators.col;
+
+    check_dimensions(constraint_lengths, generators)?;
+    if feedback.len() != k {
+        return Err(TrellisError::GeneratorShapeMismatch { constraint_lengths: k, generator_rows: feedback.len() });
+    }
+    for (&taps, &length) in feedback.iter().zip(constraint_lengths) {
+        if (taps >> (length - 1)) & 1 == 0 {
+            return Err(TrellisError::DenominatorNotDelayFree(binary_to_octal(taps)));
+        }
+    }
+
+    let memory: usize = constraint_lengths.iter().map(|&length| usize::from(length) - 1).sum();
+
+    let num_input_symbols: usize = 1 << k;
+    let num_output_symbols: usize = 1 << n;
+    let num_states: usize = 1 << memory;
+
+    // bit offset of each rail's register inside the state, rail 1 being the most significant
+    let mut register_offsets: Vec<usize> = vec![0; k];
+    for rail in (0..k.saturating_sub(1)).rev() {
+        register_offsets[rail] = register_offsets[rail + 1] + usize::from(constraint_lengths[rail + 1]) - 1;
+    }
+
+    let mut next_states: Vec2d<usize> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+    let mut outputs: Vec2d<u64> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+
+    for current_state in 0..num_states {
+        for input_symbol in 0..num_input_symbols {
+            let mut next_state: usize = 0;
+            let mut output: u64 = 0;
+
+            for rail in 0..k {
+                let register_len = usize::from(constraint_lengths[rail]) - 1;
+                let register = ((current_state >> register_offsets[rail]) & ((1 << register_len) - 1)) as u64;
+                let input_bit = ((input_symbol >> (k - 1 - rail)) & 1) as u64;
+                let feedback_bit = u64::from((register & feedback[rail]).count_ones() % 2);
+
+                // the register input followed by the register contents, newest to oldest
+                let window: u64 = ((input_bit ^ feedback_bit) << register_len) | register;
+                for j in 0..n {
+                    let bit = u64::from((window & *generators.index(rail, j)).count_ones() % 2);
+                    output ^= bit << (n - 1 - j);
+                }
+                next_state |= ((window >> 1) as usize) << register_offsets[rail];
+            }
+
+            *next_states.index_mut(current_state, input_symbol) = next_state;
+            *outputs.index_mut(current_state, input_symbol) = output;
+        }
+    }
+
+    let num_transitions: usize = constraint_lengths.iter().map(|&length| usize::from(length) - 1).max().unwrap_or(0);
+    let terminations = find_shortest_terminations(&next_states, num_transitions)?;
+
+    let termination_outputs = find_termination_outputs(&next_states, &outputs, &terminations);
+
+    let trellis: Trellis = Trellis {
+        num_input_rails: k,
+        num_input_symbols,
+        num_output_symbols,
+        num_states,
+        next_states,
+        outputs,
+        terminations,
+        termination_outputs,
+        termination_length: num_transitions,
+    };
+
+    Ok(trellis)
+}
+
+// Checks that the code fits in the state table and that the generator matrix has one row per rail
+fn check_dimensions(constraint_lengths: &[u16], code_generator: &Vec2d<u64>) -> Result<(), TrellisError> {
+
+    let k: usize = constraint_lengths.len();
+    let n: usize = code_generator.col;
+
+    if code_generator.row != k {
+        return Err(TrellisError::GeneratorShapeMismatch { constraint_lengths: k, generator_rows: code_generator.row });
+    }
+    let max_n = u64::BITS as usize;
+    if n == 0 || n > max_n {
+        return Err(TrellisError::UnsupportedOutputs { n, max_n });
+    }
+
+    for &length in constraint_lengths {
+        if length == 0 || length > u64::BITS as u16 {
+            return Err(TrellisError::UnsupportedMemory { v: length, max_v: u64::BITS as u16 });
+        }
+    }
+    // the state table holds 2^memory * 2^k entries
+    let max_k = usize::from(MAX_TABLE_BITS);
+    if k == 0 || k > max_k {
+        return Err(TrellisError::UnsupportedInputRails { k, max_k });
+    }
+    let memory: usize = constraint_lengths.iter().map(|&length| usize::from(length) - 1).sum();
+    if memory + k > usize::from(MAX_TABLE_BITS) {
+        return Err(TrellisError::StateTableTooLarge { memory, k });
+    }
+
+    Ok(())
+}
+
+// Converts a k-by-n matrix of octal generators into binary tap patterns
+fn generators_to_binary(constraint_lengths: &[u16], code_generator: &Vec2d<u64>) -> Result<Vec2d<u64>, TrellisError> {
+
+    check_dimensions(constraint_lengths, code_generator)?;
+
+    let n: usize = code_generator.col;
+    let generators: Vec<u64> = code_generator.vec.iter().enumerate()
+        .map(|(index, &octal)| octal_to_binary(octal, constraint_lengths[index / n]))
+        .collect::<Result<Vec<u64>, TrellisError>>()?;
+
+    Ok(Vec2d::new(generators, code_generator.row, n))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::poly2trellis::{poly2trellis, shift_register_trellis};
+    use crate::trellis::trellis::TrellisError;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_matches_matlab_rate_1_2() {
+        // poly2trellis(3, [7 5])
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        assert_eq!(trellis.num_input_rails, 1);
+        assert_eq!(trellis.num_output_symbols, 4);
+        assert_eq!(trellis.num_states, 4);
+        assert_eq!(trellis.next_states.vec, vec![0, 2, 0, 2, 1, 3, 1, 3]);
+        assert_eq!(trellis.outputs.vec, vec![0, 3, 3, 0, 2, 1, 1, 2]);
+        assert_eq!(trellis.termination_length, 2);
+    }
+
+    #[test]
+    fn test_rate_2_3_terminations() {
+        // poly2trellis([5 4], [23 35 0; 0 5 13])
+        let trellis = poly2trellis(&[5, 4], &Vec2d::new(vec![23, 35, 0, 0, 5, 13], 2, 3)).unwrap();
+        assert_eq!(trellis.num_input_symbols, 4);
+        assert_eq!(trellis.num_output_symbols, 8);
+        assert_eq!(trellis.num_states, 128);
+        assert_eq!(trellis.termination_length, 4);
+        for start_state in 0..trellis.num_states {
+            let mut state = start_state;
+            for input in &trellis.terminations[start_state] {
+                state = *trellis.next_states.index(state, *input);
+            }
+            assert_eq!(state, 0);
+        }
+    }
+
+    #[test]
+    fn test_invalid_generators() {
+        assert_eq!(poly2trellis(&[3], &Vec2d::new(vec![17, 5], 1, 2)).err(), Some(TrellisError::PolynomialTooWide { octal: 17, v: 3 }));
+        assert_eq!(poly2trellis(&[3, 3], &Vec2d::new(vec![7, 5], 1, 2)).err(),
+            Some(TrellisError::GeneratorShapeMismatch { constraint_lengths: 2, generator_rows: 1 }));
+    }
+
+    #[test]
+    fn test_state_table_too_large() {
+        // 3 rails of 11 taps need 2^33 state table entries
+        assert_eq!(poly2trellis(&[11, 11, 11], &Vec2d::new(vec![1; 3], 3, 1)).err(),
+            Some(TrellisError::StateTableTooLarge { memory: 30, k: 3 }));
+        assert_eq!(poly2trellis(&[64], &Vec2d::new(vec![1, 1], 1, 2)).err(),
+            Some(TrellisError::StateTableTooLarge { memory: 63, k: 1 }));
+    }
+
+    #[test]
+    fn test_feedback_without_input_tap() {
+        // the feedback 3 (octal, 011) of a 3-tap register does not tap the register input
+        assert_eq!(shift_register_trellis(&[3], &Vec2d::new(vec![0b111, 0b101], 1, 2), &[0b011]).err(),
+            Some(TrellisError::DenominatorNotDelayFree(3)));
+    }
+}
@@ -0,0 +1,140 @@
+use crate::trellis::trellis::{Trellis, TrellisError};
+use crate::trellis::terminations::{find_shortest_terminations, find_termination_outputs};
+
+use crate::vec2d::Vec2d;
+
+// A period-P puncturing pattern for a rate-k/n mother code: an n-by-P binary matrix whose entry (j, t)
+// is true when output j of the mother code is transmitted at trellis time t (mod P), and false when it
+// is punctured. Output j is bit n-1-j of the mother output symbol, i.e. the first output is the MSB.
+#[derive(Debug, Clone)]
+pub struct PuncturingPattern {
+    pub pattern: Vec2d<bool>,
+}
+
+impl PuncturingPattern {
+    pub fn new(pattern: Vec2d<bool>) -> Self {
+        Self { pattern }
+    }
+
+    pub fn period(&self) -> usize {
+        self.pattern.col
+    }
+
+    // Applies the pattern to a mother trellis. The punctured code is time-varying with period P, so its
+    // P trellis sections are merged into a single time-invariant section whose branches span one period:
+    //   1) an input symbol holds the k*P input bits of the period, the first time step in the MSBs,
+    //      so a path has the same input bits as in the mother trellis
+    //   2) an output symbol holds only the transmitted bits of the period, in transmission order, so
+    //      branch weights ignore the punctured bits
+    //   3) terminations span ceil(mu/P) periods
+    // The resulting trellis feeds the weight spectrum, IEE search and CRC search unchanged, with the
+    // trellis length counted in periods.
+    pub fn puncture(&self, trellis: &Trellis) -> Result<Trellis, TrellisError> {
+
+        let k: usize = trellis.num_input_rails;
+        let n: usize = trellis.num_output_symbols.trailing_zeros() as usize;
+        let period: usize = self.period();
+
+        if self.pattern.row != n || period == 0 {
+            return Err(TrellisError::PuncturingShapeMismatch { outputs: n, pattern_rows: self.pattern.row });
+        }
+        let kept_bits: usize = self.pattern.vec.iter().filter(|&&kept| kept).count();
+        let max_n = u64::BITS as usize;
+        if kept_bits == 0 || kept_bits > max_n {
+            return Err(TrellisError::UnsupportedOutputs { n: kept_bits, max_n });
+        }
+        // the state table holds num_states * 2^(k*P) entries
+        let max_k = usize::BITS as usize - 1 - trellis.num_states.trailing_zeros() as usize;
+        if k * period > max_k {
+            return Err(TrellisError::UnsupportedInputRails { k: k * period, max_k });
+        }
+
+        let num_input_symbols: usize = 1 << (k * period);
+        let num_states: usize = trellis.num_states;
+
+        let mut next_states: Vec2d<usize> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+        let mut outputs: Vec2d<u64> = Vec2d::new(vec![0; num_states * num_input_symbols], num_states, num_input_symbols);
+
+        for current_state in 0..num_states {
+            for input_symbol in 0..num_input_symbols {
+                let mut state = current_state;
+                let mut output: u64 = 0;
+                for time in 0..period {
+                    let input = (input_symbol >> (k * (period - 1 - time))) & ((1 << k) - 1);
+                    let mother_output = *trellis.outputs.index(state, input);
+                    for j in 0..n {
+                        if *self.pattern.index(j, time) {
+                            output = (output << 1) | ((mother_output >> (n - 1 - j)) & 1);
+                        }
+                    }
+                    state = *trellis.next_states.index(state, input);
+                }
+                *next_states.index_mut(current_state, input_symbol) = state;
+                *outputs.index_mut(current_state, input_symbol) = output;
+            }
+        }
+
+        let num_transitions: usize = trellis.termination_length.div_ceil(period);
+        let terminations = find_shortest_terminations(&next_states, num_transitions)?;
+
+        let termination_outputs = find_termination_outputs(&next_states, &outputs, &terminations);
+
+        let punctured: Trellis = Trellis {
+            num_input_rails: k * period,
+            num_input_symbols,
+            num_output_symbols: 1 << kept_bits,
+            num_states,
+            next_states,
+            outputs,
+            terminations,
+            termination_outputs,
+            termination_length: num_transitions,
+        };
+
+        Ok(punctured)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::puncturing::PuncturingPattern;
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+    use crate::trellis::trellis::TrellisError;
+    use crate::compute_ztcc_weight_spectrum::compute_ztcc_weight_spectrum;
+
+    use num_bigint::BigUint;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_unpunctured_pattern_is_identity() {
+        let mother = generate_rate_1_n_trellis(7, &[133, 171], None).unwrap();
+        let punctured = PuncturingPattern::new(Vec2d::new(vec![true, true], 2, 1)).puncture(&mother).unwrap();
+        assert_eq!(punctured.next_states.vec, mother.next_states.vec);
+        assert_eq!(punctured.outputs.vec, mother.outputs.vec);
+        assert_eq!(punctured.terminations, mother.terminations);
+    }
+
+    #[test]
+    fn test_rate_3_4_from_rate_1_2() {
+        // the standard rate-3/4 puncturing of the (133, 171) code has free distance 5
+        let mother = generate_rate_1_n_trellis(7, &[133, 171], None).unwrap();
+        let pattern = PuncturingPattern::new(Vec2d::new(vec![true, true, false, true, false, true], 2, 3));
+        let punctured = pattern.puncture(&mother).unwrap();
+        assert_eq!(punctured.num_input_rails, 3);
+        assert_eq!(punctured.num_output_symbols, 16);
+        assert_eq!(punctured.termination_length, 2);
+
+        let weight_spectrum = compute_ztcc_weight_spectrum(8, punctured).unwrap();
+        assert!(weight_spectrum[1..5].iter().all(|count| *count == BigUint::from(0_u32)));
+        assert!(weight_spectrum[5] > BigUint::from(0_u32));
+    }
+
+    #[test]
+    fn test_pattern_must_cover_every_output() {
+        let mother = generate_rate_1_n_trellis(3, &[7, 5], None).unwrap();
+        let pattern = PuncturingPattern::new(Vec2d::new(vec![true, false, true], 3, 1));
+        assert_eq!(pattern.puncture(&mother).err(), Some(TrellisError::PuncturingShapeMismatch { outputs: 2, pattern_rows: 3 }));
+    }
+}
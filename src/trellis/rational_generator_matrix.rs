@@ -0,0 +1,170 @@
+use crate::trellis::trellis::{Trellis, TrellisError, MAX_TABLE_BITS, octal_to_binary, reverse_bits, gf2_degree, gf2_gcd};
+use crate::trellis::poly2trellis::shift_register_trellis;
+
+use crate::vec2d::Vec2d;
+
+use ::gf256::p64;
+
+// A k-by-n generator matrix G(D) whose entry (i, j) is the rational transfer function
+// numerators(i, j) / denominators(i, j) from input rail i to output j.
+//
+// Entries use the octal convention of MATLAB's poly2trellis: every polynomial of row i is written
+// with constraint_lengths[i] bits, the MSB being the coefficient of D^0 and the LSB that of D^(K_i - 1).
+// A zero numerator disconnects rail i from output j (its denominator is then ignored), and every
+// other denominator must have a D^0 term for the encoder to be realizable.
+#[derive(Debug, Clone)]
+pub struct RationalGeneratorMatrix {
+    pub constraint_lengths: Vec<u16>,
+    pub numerators: Vec2d<u64>,
+    pub denominators: Vec2d<u64>,
+}
+
+impl RationalGeneratorMatrix {
+    pub fn new(constraint_lengths: Vec<u16>, numerators: Vec2d<u64>, denominators: Vec2d<u64>) -> Self {
+        Self { constraint_lengths, numerators, denominators }
+    }
+
+    // Realizes the encoder with one recursive shift register per input rail and builds its trellis.
+    // Each row is brought to a common denominator L_i(D) = lcm_j denominators(i, j), which becomes the
+    // feedback connection of rail i, and the numerators are scaled by L_i(D) / denominators(i, j).
+    // The register of rail i therefore holds max(deg L_i, deg of the scaled numerators) bits, which can
+    // be longer than constraint_lengths[i] when the denominators of a row differ, and StateTableTooLarge
+    // is returned when the registers no longer fit in the state table.
+    pub fn to_trellis(&self) -> Result<Trellis, TrellisError> {
+
+        let k: usize = self.constraint_lengths.len();
+        let n: usize = self.numerators.col;
+
+        if self.denominators.row != self.numerators.row || self.denominators.col != n {
+            return Err(TrellisError::RationalShapeMismatch {
+                numerators: (self.numerators.row, n), denominators: (self.denominators.row, self.denominators.col) });
+        }
+        if self.numerators.row != k {
+            return Err(TrellisError::GeneratorShapeMismatch { constraint_lengths: k, generator_rows: self.numerators.row });
+        }
+
+        let mut register_lengths: Vec<u16> = vec![0; k];
+        let mut generators: Vec2d<u64> = Vec2d::new(vec![0; k * n], k, n);
+        let mut feedback: Vec<u64> = vec![0; k];
+
+        for rail in 0..k {
+            let length = self.constraint_lengths[rail];
+
+            // numerators and denominators as polynomials in D, bit t holding the coefficient of D^t
+            let mut row_numerators: Vec<p64> = vec![p64(0); n];
+            let mut row_denominators: Vec<p64> = vec![p64(1); n];
+            for j in 0..n {
+                let numerator = reverse_bits(octal_to_binary(*self.numerators.index(rail, j), length)?, length);
+                if numerator == 0 {
+                    continue;
+                }
+                let octal_denominator = *self.denominators.index(rail, j);
+                let denominator = reverse_bits(octal_to_binary(octal_denominator, length)?, length);
+                if denominator & 1 == 0 {
+                    return Err(TrellisError::DenominatorNotDelayFree(octal_denominator));
+                }
+
+                // cancel common factors so the common denominator stays as small as possible
+                let common = gf2_gcd(p64(numerator), p64(denominator));
+                row_numerators[j] = p64(numerator).naive_div(common);
+                row_denominators[j] = p64(denominator).naive_div(common);
+            }
+
+            let mut common_denominator: p64 = p64(1);
+            for denominator in &row_denominators {
+                let cofactor = denominator.naive_div(gf2_gcd(common_denominator, *denominator));
+                common_denominator = checked_mul(common_denominator, cofactor)?;
+            }
+
+            let mut memory: u32 = gf2_degree(common_denominator);
+            for (numerator, denominator) in row_numerators.iter_mut().zip(&row_denominators) {
+                let cofactor = common_denominator.naive_div(*denominator);
+                *numerator = checked_mul(*numerator, cofactor)?;
+                memory = memory.max(gf2_degree(*numerator));
+            }
+
+            // back to MATLAB tap patterns on a register of memory + 1 taps
+            let register_length = memory as u16 + 1;
+            register_lengths[rail] = register_length;
+            for (j, numerator) in row_numerators.iter().enumerate() {
+                *generators.index_mut(rail, j) = reverse_bits(u64::from(*numerator), register_length);
+            }
+            feedback[rail] = reverse_bits(u64::from(common_denominator), register_length);
+        }
+
+        let memory: usize = register_lengths.iter().map(|&length| usize::from(length) - 1).sum();
+        if memory + k > usize::from(MAX_TABLE_BITS) {
+            return Err(TrellisError::StateTableTooLarge { memory, k });
+        }
+
+        shift_register_trellis(&register_lengths, &generators, &feedback)
+    }
+}
+
+// Multiplies two GF(2) polynomials, failing if the product does not fit in 64 taps
+fn checked_mul(a: p64, b: p64) -> Result<p64, TrellisError> {
+    if u64::from(a) != 0 && u64::from(b) != 0 && gf2_degree(a) + gf2_degree(b) >= u64::BITS {
+        return Err(TrellisError::UnsupportedMemory { v: (gf2_degree(a) + gf2_degree(b) + 1) as u16, max_v: u64::BITS as u16 });
+    }
+    Ok(a.naive_mul(b))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::rational_generator_matrix::RationalGeneratorMatrix;
+    use crate::trellis::generate_rate_1_n_trellis::generate_rate_1_n_trellis;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::trellis::TrellisError;
+    use crate::vec2d::Vec2d;
+
+    #[test]
+    fn test_systematic_recursive_row() {
+        // G(D) = [1, (1 + D + D^3) / (1 + D^2 + D^3)]
+        let generator = RationalGeneratorMatrix::new(vec![4], Vec2d::new(vec![10, 15], 1, 2), Vec2d::new(vec![10, 13], 1, 2));
+        let trellis = generator.to_trellis().unwrap();
+        let reference = generate_rate_1_n_trellis(4, &[13, 15], Some(13)).unwrap();
+        assert_eq!(trellis.next_states.vec, reference.next_states.vec);
+        assert_eq!(trellis.outputs.vec, reference.outputs.vec);
+    }
+
+    #[test]
+    fn test_polynomial_matrix_is_feedforward() {
+        // denominators of 1 (the D^0 tap of a K-bit register)
+        let numerators = Vec2d::new(vec![23, 35, 0, 0, 5, 13], 2, 3);
+        let denominators = Vec2d::new(vec![20, 20, 20, 10, 10, 10], 2, 3);
+        let trellis = RationalGeneratorMatrix::new(vec![5, 4], numerators.clone(), denominators).to_trellis().unwrap();
+        let reference = poly2trellis(&[5, 4], &numerators).unwrap();
+        assert_eq!(trellis.next_states.vec, reference.next_states.vec);
+        assert_eq!(trellis.outputs.vec, reference.outputs.vec);
+    }
+
+    #[test]
+    fn test_common_denominator() {
+        // G(D) = [1 / (1 + D), 1 / (1 + D + D^2)] needs a register of 3 bits for (1 + D)(1 + D + D^2)
+        let generator = RationalGeneratorMatrix::new(vec![3], Vec2d::new(vec![4, 4], 1, 2), Vec2d::new(vec![6, 7], 1, 2));
+        let trellis = generator.to_trellis().unwrap();
+        assert_eq!(trellis.num_states, 8);
+        for start_state in 0..trellis.num_states {
+            let mut state = start_state;
+            for input in &trellis.terminations[start_state] {
+                state = *trellis.next_states.index(state, *input);
+            }
+            assert_eq!(state, 0);
+        }
+    }
+
+    #[test]
+    fn test_unrealizable_denominator() {
+        let generator = RationalGeneratorMatrix::new(vec![3], Vec2d::new(vec![4, 5], 1, 2), Vec2d::new(vec![4, 3], 1, 2));
+        assert_eq!(generator.to_trellis().err(), Some(TrellisError::DenominatorNotDelayFree(3)));
+    }
+
+    #[test]
+    fn test_common_denominator_too_large() {
+        // 1 + D^13 and 1 + D + D^13 are coprime, so their lcm needs a register of 26 bits
+        let generator = RationalGeneratorMatrix::new(vec![14], Vec2d::new(vec![20000, 20000], 1, 2), Vec2d::new(vec![20001, 30001], 1, 2));
+        assert_eq!(generator.to_trellis().err(), Some(TrellisError::StateTableTooLarge { memory: 26, k: 1 }));
+    }
+}
@@ -0,0 +1,241 @@
+use crate::trellis::trellis::TrellisError;
+use crate::vec2d::Vec2d;
+
+use ::queues::{IsQueue, Queue, queue};
+
+// Finds, for every state, the shortest input sequence that drives the encoder back to state 0
+// using a breadth-first search over predecessor states. Sequences shorter than num_transitions
+// are padded with the all-zero input (state 0 must map to itself on input 0), so that every
+// termination has exactly num_transitions inputs. Fails if a state cannot reach state 0 at all,
+// or only in more than num_transitions inputs.
+pub fn find_shortest_terminations(next_states: &Vec2d<usize>, num_transitions: usize) -> Result<Vec<Vec<usize>>, TrellisError> {
+
+    let num_states = next_states.row;
+
+    // predecessor lists let the search scale to large state spaces instead of rescanning every state
+    let mut pre_states: Vec<Vec<usize>> = vec![vec![]; num_states];
+    for pre_state in 0..num_states {
+        for &target_state in next_states.row(pre_state) {
+            if pre_states[target_state].last() != Some(&pre_state) {
+                pre_states[target_state].push(pre_state);
+            }
+        }
+    }
+
+    let mut queue: Queue<usize> = queue![0];
+    let mut visited: Vec<bool> = vec![false; num_states];
+    let mut tree: Vec<usize> = vec![0; num_states];
+    visited[0] = true;
+
+    while queue.size() != 0 {
+        let target_state_option = queue.remove();
+        match target_state_option {
+            Ok(target_state) => {
+                for &pre_state in &pre_states[target_state] {
+                    if !visited[pre_state] {
+                        visited[pre_state] = true;
+                        let res = queue.add(pre_state);
+                        match res {
+                            Ok(correct) => correct,
+                            Err(error) => panic!("Problem adding to queue: {:?}", error),
+                        };
+                        tree[pre_state] = target_state;
+                    }
+                }
+            },
+            Err(_error) => break,
+        }
+    }
+
+
+    if let Some(state) = visited.iter().position(|&reached| !reached) {
+        return Err(TrellisError::UnterminableState(state));
+    }
+
+    let mut terminations: Vec<Vec<usize>> = vec![vec![]; num_states];
+
+    terminations[0].push(0);
+
+    for (current_state, termination) in terminations.iter_mut().enumerate().skip(1) {
+        let mut current_tmp = current_state;
+        while current_tmp != 0 {
+            let father_state = tree[current_tmp];
+            if let Some(index) = next_states.row(current_tmp).iter().position(|&x| x == father_state) {
+                termination.push(index);
+            }
+            current_tmp = father_state;
+        }
+        if termination.len() > num_transitions {
+            return Err(TrellisError::TerminationTooLong { state: current_state, length: termination.len(), num_transitions });
+        }
+    }
+
+    for termination in terminations.iter_mut() {
+        termination.resize(num_transitions, 0);
+    }
+
+    Ok(terminations)
+}
+
+// Finds, for every state, the input sequence of exactly num_transitions inputs that drives the encoder
+// back to state 0 with the smallest total output weight. The termination outputs are part of every
+// zero-terminated codeword, so this choice lowers the weight the terminations add to a codeword.
+// Ties are broken towards the smallest input at the earliest transition, so the result is deterministic.
+// A state that cannot reach state 0 in num_transitions gives the errors of find_shortest_terminations.
+pub fn find_min_weight_terminations(next_states: &Vec2d<usize>, outputs: &Vec2d<u64>, num_transitions: usize) -> Result<Vec<Vec<usize>>, TrellisError> {
+
+    find_shortest_terminations(next_states, num_transitions)?;
+
+    let num_states = next_states.row;
+    let num_input_symbols = next_states.col;
+    let unreachable = u32::MAX;
+
+    // weights[t][s] is the minimum output weight of the last num_transitions - t inputs from state s
+    let mut weights: Vec<Vec<u32>> = vec![vec![unreachable; num_states]; num_transitions + 1];
+    weights[num_transitions][0] = 0;
+    for time in (0..num_transitions).rev() {
+        for state in 0..num_states {
+            weights[time][state] = (0..num_input_symbols)
+                .filter_map(|input| {
+                    let remaining = weights[time + 1][*next_states.index(state, input)];
+                    (remaining != unreachable).then(|| remaining + outputs.index(state, input).count_ones())
+                })
+                .min()
+                .unwrap_or(unreachable);
+        }
+    }
+
+    (0..num_states).map(|start_state| {
+        // only when state 0 does not stay put on input 0, so the shortest terminations cannot be padded
+        if weights[0][start_state] == unreachable {
+            return Err(TrellisError::UnterminableState(start_state));
+        }
+        let mut state = start_state;
+        Ok((0..num_transitions).map(|time| {
+            let input = (0..num_input_symbols)
+                .find(|&input| {
+                    let remaining = weights[time + 1][*next_states.index(state, input)];
+                    remaining != unreachable && remaining + outputs.index(state, input).count_ones() == weights[time][state]
+                })
+                .unwrap();
+            state = *next_states.index(state, input);
+            input
+        }).collect())
+    }).collect()
+}
+
+// Output symbols emitted while following each state's termination sequence
+pub fn find_termination_outputs(next_states: &Vec2d<usize>, outputs: &Vec2d<u64>, terminations: &[Vec<usize>]) -> Vec<Vec<u64>> {
+    terminations.iter().enumerate().map(|(start_state, termination)| {
+        let mut state = start_state;
+        termination.iter().map(|&input| {
+            let output = *outputs.index(state, input);
+            state = *next_states.index(state, input);
+            output
+        }).collect()
+    }).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::terminations::{find_shortest_terminations, find_min_weight_terminations};
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::trellis::TrellisError;
+    use crate::vec2d::Vec2d;
+
+    // Enumerates, for every state, all input sequences of exactly num_transitions inputs that drive the
+    // encoder back to state 0, ordered by increasing output weight (and by input sequence within a weight).
+    // The number of sequences grows as 2^(k * num_transitions) / num_states, so this is meant for small codes.
+    fn enumerate_terminations(next_states: &Vec2d<usize>, outputs: &Vec2d<u64>, num_transitions: usize) -> Vec<Vec<Vec<usize>>> {
+
+        let num_states = next_states.row;
+        let num_input_symbols = next_states.col;
+
+        // reachable[t][s] tells whether state 0 can be reached from s with the last num_transitions - t inputs
+        let mut reachable: Vec<Vec<bool>> = vec![vec![false; num_states]; num_transitions + 1];
+        reachable[num_transitions][0] = true;
+        for time in (0..num_transitions).rev() {
+            for state in 0..num_states {
+                reachable[time][state] = next_states.row(state).iter().any(|&next_state| reachable[time + 1][next_state]);
+            }
+        }
+
+        (0..num_states).map(|start_state| {
+            let mut sequences: Vec<(u32, Vec<usize>)> = vec![];
+            let mut stack: Vec<(usize, u32, Vec<usize>)> = vec![(start_state, 0, vec![])];
+            while let Some((state, weight, inputs)) = stack.pop() {
+                if inputs.len() == num_transitions {
+                    sequences.push((weight, inputs));
+                    continue;
+                }
+                for input in 0..num_input_symbols {
+                    let next_state = *next_states.index(state, input);
+                    if reachable[inputs.len() + 1][next_state] {
+                        let mut next_inputs = inputs.clone();
+                        next_inputs.push(input);
+                        stack.push((next_state, weight + outputs.index(state, input).count_ones(), next_inputs));
+                    }
+                }
+            }
+            sequences.sort();
+            sequences.into_iter().map(|(_, inputs)| inputs).collect()
+        }).collect()
+    }
+
+    fn weight(outputs: &[u64]) -> u32 {
+        outputs.iter().map(|output| output.count_ones()).sum()
+    }
+
+    #[test]
+    fn test_min_weight_terminations() {
+        for (v, numerators, denominator) in [(4, vec![13], 15), (4, vec![15, 17], 13), (7, vec![107, 135, 133], 141)] {
+            let mut trellis = generate_feedback_trellis(v, &numerators, denominator).unwrap();
+            let shortest_outputs = trellis.termination_outputs.clone();
+            trellis.use_min_weight_terminations().unwrap();
+            let all = enumerate_terminations(&trellis.next_states, &trellis.outputs, trellis.termination_length);
+
+            for start_state in 0..trellis.num_states {
+                let termination = &trellis.terminations[start_state];
+                assert_eq!(termination.len(), trellis.termination_length);
+                assert_eq!(&all[start_state][0], termination);
+
+                let mut state = start_state;
+                for (&input, &output) in termination.iter().zip(&trellis.termination_outputs[start_state]) {
+                    assert_eq!(*trellis.outputs.index(state, input), output);
+                    state = *trellis.next_states.index(state, input);
+                }
+                assert_eq!(state, 0);
+                assert!(weight(&trellis.termination_outputs[start_state]) <= weight(&shortest_outputs[start_state]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_enumerate_terminations_count() {
+        // the input sequences of mu transitions map evenly onto the states for these feedback codes,
+        // so each state has 2^(k * mu) / 2^(v - 1) terminations: 1 for the 3/4 code and 2 for the 2/3 code
+        for (v, numerators, denominator, expected) in [(7, vec![107, 135, 133], 141, 1), (4, vec![15, 17], 13, 2)] {
+            let trellis = generate_feedback_trellis(v, &numerators, denominator).unwrap();
+            let all = enumerate_terminations(&trellis.next_states, &trellis.outputs, trellis.termination_length);
+            for terminations in all {
+                assert_eq!(terminations.len(), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_shortest_terminations_must_fit() {
+        // a shift register of two input bits: every state returns to 0 on input 0 within 2 transitions
+        let next_states = Vec2d::new(vec![0, 2, 0, 2, 1, 3, 1, 3], 4, 2);
+        assert_eq!(find_shortest_terminations(&next_states, 2), Ok(vec![vec![0, 0], vec![0, 0], vec![0, 0], vec![0, 0]]));
+        assert_eq!(find_shortest_terminations(&next_states, 1), Err(TrellisError::TerminationTooLong { state: 2, length: 2, num_transitions: 1 }));
+
+        // state 1 only ever leads back to itself
+        let next_states = Vec2d::new(vec![0, 1, 1, 1], 2, 2);
+        assert_eq!(find_shortest_terminations(&next_states, 4), Err(TrellisError::UnterminableState(1)));
+        let outputs = Vec2d::new(vec![0, 3, 1, 2], 2, 2);
+        assert_eq!(find_min_weight_terminations(&next_states, &outputs, 4), Err(TrellisError::UnterminableState(1)));
+    }
+}
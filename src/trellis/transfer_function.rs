@@ -0,0 +1,326 @@
+use crate::trellis::trellis::{Trellis, TrellisError};
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::{One, Signed, Zero};
+
+// Generating function of the error events of a code (the paths that leave state 0 with a nonzero
+// input and first return to it), as the ratio of two polynomials in I and D. numerator[w][d] and
+// denominator[w][d] are the coefficients of I^w D^d; the transfer function T(D) of
+// find_transfer_function has the single row w = 0. The ratio is not necessarily in lowest terms,
+// but the denominator always has constant term 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransferFunction {
+    pub numerator: Vec<Vec<BigInt>>,
+    pub denominator: Vec<Vec<BigInt>>,
+}
+
+impl TransferFunction {
+    // The power series of the transfer function up to D^max_degree: series[w][d] is the number of
+    // error events of input weight w and output weight d
+    pub fn series(&self, max_degree: usize) -> Vec<Vec<BigUint>> {
+        // the coefficients of D^d of the numerator and denominator, as polynomials in I
+        let column = |poly: &Vec<Vec<BigInt>>, d: usize| -> Vec<BigInt> {
+            trim(poly.iter().map(|row| row.get(d).cloned().unwrap_or_else(BigInt::zero)).collect())
+        };
+
+        // q_d = numerator_d - sum_{j=1..d} denominator_j q_{d-j}, since denominator_0 = 1
+        let mut series_columns: Vec<Vec<BigInt>> = Vec::with_capacity(max_degree + 1);
+        for d in 0..=max_degree {
+            let mut q = column(&self.numerator, d);
+            for j in 1..=d {
+                let product = multiply_in_i(&column(&self.denominator, j), &series_columns[d - j]);
+                q = subtract_in_i(&q, &product);
+            }
+            series_columns.push(q);
+        }
+
+        let num_rows = series_columns.iter().map(|q| q.len()).max().unwrap_or(0).max(1);
+        (0..num_rows).map(|w| {
+            series_columns.iter().map(|q| {
+                q.get(w).map_or_else(BigUint::zero, |c| c.to_biguint().expect("negative error event count"))
+            }).collect()
+        }).collect()
+    }
+
+    // The series of T(D) = T(1, D): the number of error events of every output weight up to max_degree
+    pub fn output_weight_series(&self, max_degree: usize) -> Vec<BigUint> {
+        let series = self.series(max_degree);
+        (0..=max_degree).map(|d| series.iter().map(|row| &row[d]).sum()).collect()
+    }
+}
+
+// Finds T(D), the sum of D^(output weight) over all error events, in closed form
+pub fn find_transfer_function(trellis: &Trellis) -> Result<TransferFunction, TrellisError> {
+    transfer_function(trellis, false)
+}
+
+// Finds T(I, D), the sum of I^(input weight) D^(output weight) over all error events, in closed form
+pub fn find_input_output_transfer_function(trellis: &Trellis) -> Result<TransferFunction, TrellisError> {
+    transfer_function(trellis, true)
+}
+
+// Splits state 0 into a start and an end node. With A the branch polynomials between the other
+// states, b those from the start into them, c those from them to the end and t those straight from
+// start to end (nonzero inputs only), T = t + b (I - A)^-1 c. By the Schur complement this is
+//     det([[I - A, c], [-b, t]]) / det(I - A),
+// and fraction-free (Bareiss) elimination of the bordered matrix gives both determinants exactly:
+// the leading principal minors are the pivots, the last of them det(I - A), and the last entry is
+// det of the whole matrix. Without zero-output cycles I - A is unit lower triangular at D = 0 up to
+// a reordering of the states, so every leading minor has constant term 1 and no pivot is zero.
+fn transfer_function(trellis: &Trellis, with_input_weight: bool) -> Result<TransferFunction, TrellisError> {
+
+    let num_states = trellis.num_states;
+    let size = num_states;
+    let last = size - 1;
+    // rows and columns 0..last are the states 1..num_states, and last is state 0 (start row, end column)
+    let index = |state: usize| if state == 0 { last } else { state - 1 };
+
+    let mut matrix: Vec<Vec<Vec<Vec<BigInt>>>> = vec![vec![vec![]; size]; size];
+    for (i, row) in matrix.iter_mut().enumerate().take(last) {
+        row[i] = vec![vec![BigInt::one()]];
+    }
+    for state in 0..num_states {
+        for input in 0..trellis.num_input_symbols {
+            let next_state = *trellis.next_states.index(state, input);
+            if state == 0 && next_state == 0 && input == 0 {
+                continue;
+            }
+            let input_weight = if with_input_weight { input.count_ones() as usize } else { 0 };
+            let output_weight = trellis.outputs.index(state, input).count_ones() as usize;
+            let branch = monomial(input_weight, output_weight);
+            let (i, j) = (index(state), index(next_state));
+            // the A and b blocks are negated, c and t are not
+            let entry = &mut matrix[i][j];
+            *entry = if j == last { add(entry, &branch) } else { subtract(entry, &branch) };
+        }
+    }
+
+    let mut denominator: Vec<Vec<BigInt>> = vec![vec![BigInt::one()]];
+    let mut previous_pivot: Vec<Vec<BigInt>> = vec![vec![BigInt::one()]];
+    for pivot_index in 0..last {
+        let pivot = matrix[pivot_index][pivot_index].clone();
+        if pivot.is_empty() {
+            return Err(TrellisError::ZeroOutputCycle);
+        }
+        for i in (pivot_index + 1)..size {
+            for j in (pivot_index + 1)..size {
+                let cross = multiply(&matrix[i][pivot_index], &matrix[pivot_index][j]);
+                let numerator = subtract(&multiply(&pivot, &matrix[i][j]), &cross);
+                matrix[i][j] = exact_divide(&numerator, &previous_pivot);
+            }
+        }
+        previous_pivot = pivot;
+        if pivot_index + 1 == last {
+            denominator = previous_pivot.clone();
+        }
+    }
+    let mut numerator = matrix[last][last].clone();
+
+    // the constant term of det(I - A) is 1 unless there is a zero-output cycle
+    let constant_term: Vec<BigInt> = denominator.iter().map(|row| row.first().cloned().unwrap_or_else(BigInt::zero)).collect();
+    if trim(constant_term.clone()).len() != 1 || !constant_term[0].abs().is_one() {
+        return Err(TrellisError::ZeroOutputCycle);
+    }
+    if constant_term[0].is_negative() {
+        numerator = subtract(&[], &numerator);
+        denominator = subtract(&[], &denominator);
+    }
+
+    Ok(TransferFunction {
+        numerator: rows_or_zero(numerator),
+        denominator: rows_or_zero(denominator),
+    })
+}
+
+// Bivariate polynomials are Vec<Vec<BigInt>> indexed [w][d] with no trailing zero rows (the zero
+// polynomial has no rows), and polynomials in I alone are Vec<BigInt> with no trailing zeros
+
+fn monomial(w: usize, d: usize) -> Vec<Vec<BigInt>> {
+    let mut rows = vec![vec![]; w + 1];
+    rows[w] = vec![BigInt::zero(); d];
+    rows[w].push(BigInt::one());
+    rows
+}
+
+fn rows_or_zero(poly: Vec<Vec<BigInt>>) -> Vec<Vec<BigInt>> {
+    if poly.is_empty() { vec![vec![BigInt::zero()]] } else { poly }
+}
+
+fn trim(mut poly: Vec<BigInt>) -> Vec<BigInt> {
+    while poly.last().is_some_and(Zero::is_zero) {
+        poly.pop();
+    }
+    poly
+}
+
+fn trim_rows(mut poly: Vec<Vec<BigInt>>) -> Vec<Vec<BigInt>> {
+    for row in poly.iter_mut() {
+        *row = trim(std::mem::take(row));
+    }
+    while poly.last().is_some_and(Vec::is_empty) {
+        poly.pop();
+    }
+    poly
+}
+
+fn add(a: &[Vec<BigInt>], b: &[Vec<BigInt>]) -> Vec<Vec<BigInt>> {
+    combine(a, b, false)
+}
+
+fn subtract(a: &[Vec<BigInt>], b: &[Vec<BigInt>]) -> Vec<Vec<BigInt>> {
+    combine(a, b, true)
+}
+
+fn subtract_in_i(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    let mut result = a.to_vec();
+    if result.len() < b.len() {
+        result.resize(b.len(), BigInt::zero());
+    }
+    for (r, c) in result.iter_mut().zip(b) {
+        *r -= c;
+    }
+    trim(result)
+}
+
+fn combine(a: &[Vec<BigInt>], b: &[Vec<BigInt>], negate: bool) -> Vec<Vec<BigInt>> {
+    let mut result = a.to_vec();
+    if result.len() < b.len() {
+        result.resize(b.len(), vec![]);
+    }
+    for (row, b_row) in result.iter_mut().zip(b) {
+        if row.len() < b_row.len() {
+            row.resize(b_row.len(), BigInt::zero());
+        }
+        for (r, c) in row.iter_mut().zip(b_row) {
+            if negate { *r -= c } else { *r += c }
+        }
+    }
+    trim_rows(result)
+}
+
+fn multiply_in_i(a: &[BigInt], b: &[BigInt]) -> Vec<BigInt> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut result = vec![BigInt::zero(); a.len() + b.len() - 1];
+    for (i, x) in a.iter().enumerate() {
+        if x.is_zero() {
+            continue;
+        }
+        for (j, y) in b.iter().enumerate() {
+            result[i + j] += x * y;
+        }
+    }
+    trim(result)
+}
+
+fn multiply(a: &[Vec<BigInt>], b: &[Vec<BigInt>]) -> Vec<Vec<BigInt>> {
+    if a.is_empty() || b.is_empty() {
+        return vec![];
+    }
+    let mut result: Vec<Vec<BigInt>> = vec![vec![]; a.len() + b.len() - 1];
+    for (i, a_row) in a.iter().enumerate() {
+        for (j, b_row) in b.iter().enumerate() {
+            let product = multiply_in_i(a_row, b_row);
+            let row = &mut result[i + j];
+            if row.len() < product.len() {
+                row.resize(product.len(), BigInt::zero());
+            }
+            for (r, c) in row.iter_mut().zip(product) {
+                *r += c;
+            }
+        }
+    }
+    trim_rows(result)
+}
+
+// The highest power of I and then of D with a nonzero coefficient (lexicographic order)
+fn leading_term(poly: &[Vec<BigInt>]) -> (usize, usize) {
+    let w = poly.len() - 1;
+    (w, poly[w].len() - 1)
+}
+
+// a / b for polynomials that divide exactly, by long division on the lexicographic leading terms
+// (the leading term of a product is the product of the leading terms, so every step is exact)
+fn exact_divide(a: &[Vec<BigInt>], b: &[Vec<BigInt>]) -> Vec<Vec<BigInt>> {
+    let (b_w, b_d) = leading_term(b);
+    let b_lead = &b[b_w][b_d];
+    let mut remainder = a.to_vec();
+    let mut quotient: Vec<Vec<BigInt>> = vec![];
+    while !remainder.is_empty() {
+        let (r_w, r_d) = leading_term(&remainder);
+        assert!(r_w >= b_w && r_d >= b_d, "inexact polynomial division");
+        let coefficient = &remainder[r_w][r_d] / b_lead;
+        assert!((&coefficient * b_lead) == remainder[r_w][r_d], "inexact polynomial division");
+        let mut term = monomial(r_w - b_w, r_d - b_d);
+        term[r_w - b_w][r_d - b_d] = coefficient;
+        remainder = subtract(&remainder, &multiply(&term, b));
+        quotient = add(&quotient, &term);
+    }
+    quotient
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::trellis::transfer_function::{find_transfer_function, find_input_output_transfer_function};
+    use crate::trellis::distance::find_free_distance;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::trellis::TrellisError;
+    use crate::vec2d::Vec2d;
+
+    use num_bigint::{BigInt, BigUint};
+
+    #[test]
+    fn test_transfer_function_7_5() {
+        // T(I, D) = I D^5 / (1 - 2 I D)
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let transfer_function = find_input_output_transfer_function(&trellis).unwrap();
+        let series = transfer_function.series(9);
+        for (w, row) in series.iter().enumerate() {
+            for (d, count) in row.iter().enumerate() {
+                let expected = if d >= 5 && w == d - 4 { BigUint::from(1_u32) << (d - 5) } else { BigUint::from(0_u32) };
+                assert_eq!(*count, expected, "I^{} D^{}", w, d);
+            }
+        }
+
+        let transfer_function = find_transfer_function(&trellis).unwrap();
+        let expected: Vec<BigUint> = (0..10).map(|d| if d < 5 { 0_u32.into() } else { BigUint::from(1_u32) << (d - 5) }).collect();
+        assert_eq!(transfer_function.output_weight_series(9), expected);
+        // the ratio of the determinants equals D^5 / (1 - 2D): numerator (1 - 2D) = D^5 denominator
+        let numerator = &transfer_function.numerator[0];
+        let denominator = &transfer_function.denominator[0];
+        let len = numerator.len().max(denominator.len() + 5) + 1;
+        let coefficient = |poly: &Vec<BigInt>, d: usize| poly.get(d).cloned().unwrap_or_default();
+        for d in 0..len {
+            let lhs = coefficient(numerator, d) - if d > 0 { BigInt::from(2) * coefficient(numerator, d - 1) } else { BigInt::from(0) };
+            let rhs = if d >= 5 { coefficient(denominator, d - 5) } else { BigInt::from(0) };
+            assert_eq!(lhs, rhs);
+        }
+    }
+
+    #[test]
+    fn test_series_starts_at_free_distance() {
+        let trellis = generate_feedback_trellis(4, &[15, 17], 13).unwrap();
+        let free_distance = find_free_distance(&trellis);
+        let d_free = free_distance.free_distance as usize;
+        let series = find_transfer_function(&trellis).unwrap().output_weight_series(d_free);
+        assert!(series[..d_free].iter().all(|count| *count == BigUint::from(0_u32)));
+        assert_eq!(series[d_free], BigUint::from(free_distance.multiplicity.unwrap()));
+
+        // the 64-state (171, 133) code has 11 error events of weight d_free = 10
+        let trellis = poly2trellis(&[7], &Vec2d::new(vec![171, 133], 1, 2)).unwrap();
+        let series = find_transfer_function(&trellis).unwrap().output_weight_series(11);
+        assert_eq!(series[10], BigUint::from(11_u32));
+        assert!(series[..10].iter().all(|count| *count == BigUint::from(0_u32)));
+    }
+
+    #[test]
+    fn test_zero_output_cycle_has_no_series() {
+        // the catastrophic (3, 3) code loops in state 1 with zero output
+        let trellis = poly2trellis(&[2], &Vec2d::new(vec![3, 3], 1, 2)).unwrap();
+        assert_eq!(find_transfer_function(&trellis), Err(TrellisError::ZeroOutputCycle));
+    }
+}
@@ -1,11 +1,140 @@
 use crate::vec2d::Vec2d;
-use ::gf256::p16;
+use crate::trellis::terminations::{find_min_weight_terminations, find_termination_outputs};
 
+use std::fmt;
+
+use ::gf256::p64;
+
+// The state and output tables hold 2^memory * 2^k entries of 16 bytes each, at most 2^26 of them (1 GiB)
+pub const MAX_TABLE_BITS: u16 = 26;
+
+#[derive(Clone)]
 pub struct Trellis {
+    pub num_input_rails: usize,
     pub num_input_symbols: usize,
     pub num_output_symbols: usize,
     pub num_states: usize,
-    pub next_states: Vec2d<p16>,
-    pub outputs:  Vec2d<p16>,
-    pub terminations: Vec<Vec<u16>>,
-}
\ No newline at end of file
+    pub next_states: Vec2d<usize>,
+    pub outputs:  Vec2d<u64>,
+    pub terminations: Vec<Vec<usize>>,
+    pub termination_outputs: Vec<Vec<u64>>,
+    pub termination_length: usize,
+}
+
+impl Trellis {
+    // Replaces each state's termination with the length-mu input sequence of minimum output weight,
+    // instead of the shortest path to state 0 padded with zero inputs
+    pub fn use_min_weight_terminations(&mut self) -> Result<(), TrellisError> {
+        self.terminations = find_min_weight_terminations(&self.next_states, &self.outputs, self.termination_length)?;
+        self.termination_outputs = find_termination_outputs(&self.next_states, &self.outputs, &self.terminations);
+        Ok(())
+    }
+}
+
+// Reasons a requested code cannot be turned into a Trellis
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrellisError {
+    // A generator contains the digit 8 or 9, or is too long to fit in 64 bits once converted from octal
+    InvalidOctal(u64),
+    // The number of memory elements is outside of what the GF(2) arithmetic and state indexing can represent
+    UnsupportedMemory { v: u16, max_v: u16 },
+    // The number of input rails is zero or too large for the input/output symbols to fit in 64 bits
+    UnsupportedInputRails { k: usize, max_k: usize },
+    // A generator polynomial has degree v or larger, so it does not fit in the encoder's v taps
+    PolynomialTooWide { octal: u64, v: u16 },
+    // The feedback polynomial has no D^0 term, so the encoder is not realizable
+    DenominatorNotDelayFree(u64),
+    // The code generator matrix does not have one row per constraint length
+    GeneratorShapeMismatch { constraint_lengths: usize, generator_rows: usize },
+    // The number of outputs is zero or too large for an output symbol to fit in 64 bits
+    UnsupportedOutputs { n: usize, max_n: usize },
+    // The numerator and denominator matrices of a rational generator matrix have different (rows, columns)
+    RationalShapeMismatch { numerators: (usize, usize), denominators: (usize, usize) },
+    // A puncturing pattern does not have one row per output of the mother code, or has no columns
+    PuncturingShapeMismatch { outputs: usize, pattern_rows: usize },
+    // The trellis has a zero-output cycle away from the all-zero loop, so error events of finite
+    // weight can be arbitrarily long and the transfer function has no power series in D
+    ZeroOutputCycle,
+    // A state cannot be driven back to state 0 by any input sequence
+    UnterminableState(usize),
+    // The shortest input sequence from a state back to state 0 is longer than the termination length
+    TerminationTooLong { state: usize, length: usize, num_transitions: usize },
+    // The 2^memory * 2^k entries of the state table are more than MAX_TABLE_BITS allows
+    StateTableTooLarge { memory: usize, k: usize },
+}
+
+impl fmt::Display for TrellisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrellisError::InvalidOctal(octal) =>
+                write!(f, "{} is not a valid octal generator that fits in 64 bits", octal),
+            TrellisError::UnsupportedMemory { v, max_v } =>
+                write!(f, "v = {} is not supported, v must be between 2 and {}", v, max_v),
+            TrellisError::UnsupportedInputRails { k, max_k } =>
+                write!(f, "k = {} input rails is not supported, k must be between 1 and {}", k, max_k),
+            TrellisError::PolynomialTooWide { octal, v } =>
+                write!(f, "generator {} (octal) has more than v = {} taps", octal, v),
+            TrellisError::DenominatorNotDelayFree(octal) =>
+                write!(f, "denominator {} (octal) has no D^0 term", octal),
+            TrellisError::GeneratorShapeMismatch { constraint_lengths, generator_rows } =>
+                write!(f, "{} constraint lengths were given for a code generator with {} rows", constraint_lengths, generator_rows),
+            TrellisError::UnsupportedOutputs { n, max_n } =>
+                write!(f, "n = {} outputs is not supported, n must be between 1 and {}", n, max_n),
+            TrellisError::RationalShapeMismatch { numerators, denominators } =>
+                write!(f, "numerators are {}-by-{} but denominators are {}-by-{}",
+                    numerators.0, numerators.1, denominators.0, denominators.1),
+            TrellisError::PuncturingShapeMismatch { outputs, pattern_rows } =>
+                write!(f, "puncturing pattern has {} rows for a mother code with {} outputs", pattern_rows, outputs),
+            TrellisError::ZeroOutputCycle =>
+                write!(f, "the trellis has a zero-output cycle, so the transfer function has no power series"),
+            TrellisError::UnterminableState(state) =>
+                write!(f, "state {} cannot be driven back to state 0", state),
+            TrellisError::TerminationTooLong { state, length, num_transitions } =>
+                write!(f, "state {} needs {} transitions to return to state 0, more than the {} of the termination", state, length, num_transitions),
+            TrellisError::StateTableTooLarge { memory, k } =>
+                write!(f, "{} memory elements and k = {} input rails need 2^{} state table entries, at most 2^{} are supported",
+                    memory, k, memory + k, MAX_TABLE_BITS),
+        }
+    }
+}
+
+impl std::error::Error for TrellisError {}
+
+// Reads the decimal digits of an octal generator (e.g. 141) as the binary polynomial they denote
+// and checks that it fits in num_taps bits
+pub fn octal_to_binary(octal: u64, num_taps: u16) -> Result<u64, TrellisError> {
+    let binary = u64::from_str_radix(&octal.to_string(), 8).map_err(|_| TrellisError::InvalidOctal(octal))?;
+    if u64::BITS - binary.leading_zeros() > u32::from(num_taps) {
+        return Err(TrellisError::PolynomialTooWide { octal, v: num_taps });
+    }
+    Ok(binary)
+}
+
+// Writes the binary digits of a polynomial as the decimal digits of its octal form (the inverse of octal_to_binary)
+pub fn binary_to_octal(binary: u64) -> u64 {
+    format!("{:o}", binary).parse().expect("polynomial too wide to write in octal digits")
+}
+
+// Reverses the lowest `width` bits of x
+pub fn reverse_bits(x: u64, width: u16) -> u64 {
+    if width == 0 {
+        return 0;
+    }
+    x.reverse_bits() >> (u64::BITS - u32::from(width))
+}
+
+// Degree of a GF(2) polynomial (bit t holding the coefficient of D^t), taking the zero polynomial to have degree 0
+pub fn gf2_degree(poly: p64) -> u32 {
+    (u64::BITS - u64::from(poly).leading_zeros()).saturating_sub(1)
+}
+
+// Greatest common divisor of two GF(2) polynomials
+pub fn gf2_gcd(a: p64, b: p64) -> p64 {
+    let (mut a, mut b) = (a, b);
+    while u64::from(b) != 0 {
+        let remainder = a.naive_rem(b);
+        a = b;
+        b = remainder;
+    }
+    a
+}
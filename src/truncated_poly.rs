@@ -0,0 +1,191 @@
+use std::ops::Add;
+use std::ops::AddAssign;
+use std::ops::Mul;
+use std::ops::MulAssign;
+
+// Polynomial in D with the terms of degree above max_degree dropped, i.e. arithmetic modulo
+// D^(max_degree+1). Weight enumerators only need the coefficients up to the largest distance of
+// interest, and truncating them keeps every product at (max_degree+1)^2 coefficient products no
+// matter how long the trellis is.
+
+// zero() and one() know nothing about the truncation, so they get max_degree = usize::MAX, and
+// sums and products keep the smaller max_degree of their operands. Equality only compares the
+// coefficients, so a constant is equal to itself at any truncation.
+#[derive(Clone, Debug)]
+pub struct TruncatedPoly<T> {
+    coeffs: Vec<T>,
+    max_degree: usize,
+}
+
+impl<T: Clone + num_traits::Zero> TruncatedPoly<T> {
+    pub fn new(mut coeffs: Vec<T>, max_degree: usize) -> TruncatedPoly<T> {
+        coeffs.truncate(max_degree.saturating_add(1));
+        let mut poly = TruncatedPoly { coeffs, max_degree };
+        poly.trim();
+        poly
+    }
+
+    // D^degree, which is zero if degree is above max_degree
+    pub fn monomial(degree: usize, max_degree: usize) -> TruncatedPoly<T>
+    where
+        T: num_traits::One,
+    {
+        if degree > max_degree {
+            return TruncatedPoly { coeffs: vec![], max_degree };
+        }
+        let mut coeffs = vec![T::zero(); degree];
+        coeffs.push(T::one());
+        TruncatedPoly { coeffs, max_degree }
+    }
+
+    // the coefficients from D^0 up, without trailing zeros
+    pub fn coeffs(&self) -> &[T] {
+        &self.coeffs
+    }
+
+    pub fn into_coeffs(self) -> Vec<T> {
+        self.coeffs
+    }
+
+    pub fn max_degree(&self) -> usize {
+        self.max_degree
+    }
+
+    fn trim(&mut self) {
+        while self.coeffs.last().is_some_and(|c| c.is_zero()) {
+            self.coeffs.pop();
+        }
+    }
+}
+
+impl<T: PartialEq> PartialEq for TruncatedPoly<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.coeffs == other.coeffs
+    }
+}
+
+impl<T: Clone + num_traits::Zero + AddAssign> Add for TruncatedPoly<T> {
+    type Output = TruncatedPoly<T>;
+
+    fn add(mut self, rhs: TruncatedPoly<T>) -> TruncatedPoly<T> {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Clone + num_traits::Zero + AddAssign> AddAssign for TruncatedPoly<T> {
+    fn add_assign(&mut self, rhs: TruncatedPoly<T>) {
+        self.max_degree = self.max_degree.min(rhs.max_degree);
+        self.coeffs.truncate(self.max_degree.saturating_add(1));
+        let len = rhs.coeffs.len().min(self.max_degree.saturating_add(1));
+        if self.coeffs.len() < len {
+            self.coeffs.resize(len, T::zero());
+        }
+        for (coeff, rhs_coeff) in self.coeffs.iter_mut().zip(rhs.coeffs.into_iter().take(len)) {
+            *coeff += rhs_coeff;
+        }
+        self.trim();
+    }
+}
+
+impl<T> Mul for TruncatedPoly<T>
+where
+    T: Clone + num_traits::Zero + AddAssign,
+    for<'a> &'a T: Mul<&'a T, Output = T>,
+{
+    type Output = TruncatedPoly<T>;
+
+    fn mul(self, rhs: TruncatedPoly<T>) -> TruncatedPoly<T> {
+        let max_degree = self.max_degree.min(rhs.max_degree);
+        if self.coeffs.is_empty() || rhs.coeffs.is_empty() {
+            return TruncatedPoly { coeffs: vec![], max_degree };
+        }
+        let len = (self.coeffs.len() + rhs.coeffs.len() - 1).min(max_degree.saturating_add(1));
+        let mut coeffs = vec![T::zero(); len];
+        for (i, a) in self.coeffs.iter().enumerate().take(len) {
+            if a.is_zero() {
+                continue;
+            }
+            for (j, b) in rhs.coeffs.iter().enumerate().take(len - i) {
+                coeffs[i + j] += a * b;
+            }
+        }
+        let mut poly = TruncatedPoly { coeffs, max_degree };
+        poly.trim();
+        poly
+    }
+}
+
+impl<T> MulAssign for TruncatedPoly<T>
+where
+    T: Clone + num_traits::Zero + AddAssign,
+    for<'a> &'a T: Mul<&'a T, Output = T>,
+{
+    fn mul_assign(&mut self, rhs: TruncatedPoly<T>) {
+        let lhs = std::mem::replace(self, TruncatedPoly { coeffs: vec![], max_degree: 0 });
+        *self = lhs * rhs;
+    }
+}
+
+impl<T: Clone + num_traits::Zero + AddAssign> num_traits::Zero for TruncatedPoly<T> {
+    fn zero() -> Self {
+        TruncatedPoly { coeffs: vec![], max_degree: usize::MAX }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.coeffs.is_empty()
+    }
+}
+
+impl<T> num_traits::One for TruncatedPoly<T>
+where
+    T: Clone + num_traits::Zero + num_traits::One + AddAssign,
+    for<'a> &'a T: Mul<&'a T, Output = T>,
+{
+    fn one() -> Self {
+        TruncatedPoly { coeffs: vec![T::one()], max_degree: usize::MAX }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::truncated_poly::TruncatedPoly;
+    use crate::poly_wrapper::{PolyToWrapped, WrappedToPoly};
+
+    use nalgebra::DMatrix;
+    use num_traits::{One, Zero};
+    use polynomen::Poly;
+
+    #[test]
+    fn test_product_is_truncated() {
+        // (1 + D)^2 = 1 + 2D + D^2, and D^2 drops out modulo D^2
+        let a = TruncatedPoly::new(vec![1_u64, 1], 1);
+        assert_eq!((a.clone() * a.clone()).coeffs(), &[1, 2]);
+        let b = TruncatedPoly::new(vec![1_u64, 1], 5);
+        assert_eq!((b.clone() * b.clone()).coeffs(), &[1, 2, 1]);
+        assert_eq!((a.clone() * b).max_degree(), 1);
+        assert_eq!(TruncatedPoly::<u64>::monomial(3, 2), TruncatedPoly::zero());
+        assert_eq!(a.clone() * TruncatedPoly::one(), a);
+        assert_eq!((a.clone() + TruncatedPoly::new(vec![0, 0, 7], 9)).coeffs(), &[1, 1]);
+    }
+
+    #[test]
+    fn test_matrix_power_matches_full_polynomials() {
+        let entries: Vec<Vec<u64>> = vec![vec![1, 1], vec![0, 2], vec![0, 0, 1], vec![3]];
+        let truncated = DMatrix::from_fn(2, 2, |i, j| TruncatedPoly::new(entries[2 * i + j].clone(), 3));
+        let full = DMatrix::from_fn(2, 2, |i, j| Poly::new_from_coeffs(&entries[2 * i + j]).wrap());
+        let mut truncated_power = DMatrix::<TruncatedPoly<u64>>::identity(2, 2);
+        let mut full_power = full.clone();
+        truncated_power *= &truncated;
+        for _ in 1..5 {
+            truncated_power *= &truncated;
+            full_power *= &full;
+        }
+        for (t, f) in truncated_power.iter().zip(full_power.iter()) {
+            let coeffs: Vec<u64> = f.clone().unwrap().coeffs().into_iter().take(4).collect();
+            let expected = TruncatedPoly::new(coeffs, 3);
+            assert_eq!(t, &expected);
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use crate::trellis::trellis::Trellis;
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use serde::{Deserialize, Serialize};
+
+// Channel over which a union bound is evaluated
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channel {
+    // BPSK over AWGN at eb_n0_db (Eb per message bit) with a code of the given rate
+    Awgn { eb_n0_db: f64, rate: f64 },
+    // binary symmetric channel with the given crossover probability
+    Bsc { crossover: f64 },
+}
+
+// Union bounds at a set of channel parameters (Eb/N0 in dB or crossover probabilities), as
+// written to union_bounds_awgn.json and union_bounds_bsc.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnionBounds {
+    pub channel_parameters: Vec<f64>,
+    pub frame_error: Vec<f64>,
+    pub bit_error: Vec<f64>,
+    // None (and left out of the JSON) if no unique DSO CRC was identified
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub undetected_error: Option<Vec<f64>>,
+}
+
+// Probability that ML decoding prefers a codeword at Hamming distance d from the transmitted one
+pub fn pairwise_error_probability(channel: Channel, d: usize) -> f64 {
+    match channel {
+        Channel::Awgn { eb_n0_db, rate } => {
+            let eb_n0 = 10_f64.powf(eb_n0_db / 10.0);
+            q_function((2.0 * d as f64 * rate * eb_n0).sqrt())
+        },
+        Channel::Bsc { crossover } => {
+            // more than d/2 of the d positions flipped, and a coin toss for exactly d/2
+            let p = crossover;
+            assert!((0.0..=1.0).contains(&p), "crossover probability {} is not in [0, 1]", p);
+            // the logarithms below need 0 < p < 1
+            if p == 0.0 || p == 1.0 {
+                let num_flipped = if p == 0.0 { 0 } else { d };
+                return if 2 * num_flipped > d { 1.0 } else if 2 * num_flipped == d { 0.5 } else { 0.0 };
+            }
+            let mut probability = 0.0;
+            let mut log_binomial = 0.0; // ln C(d, e)
+            for e in 0..=d {
+                if e > 0 {
+                    log_binomial += ((d - e + 1) as f64).ln() - (e as f64).ln();
+                }
+                let term = (log_binomial + e as f64 * p.ln() + (d - e) as f64 * (1.0 - p).ln()).exp();
+                if 2 * e > d {
+                    probability += term;
+                } else if 2 * e == d {
+                    probability += 0.5 * term;
+                }
+            }
+            probability
+        },
+    }
+}
+
+// Union bound on the frame error probability, with spectrum[d] codewords of weight d
+// (the weight spectrum from compute_ztcc_weight_spectrum, index 0 being the all-zero codeword)
+pub fn frame_error_union_bound(spectrum: &[f64], channel: Channel) -> f64 {
+    spectrum.iter().enumerate().skip(1)
+        .filter(|&(_, &count)| count != 0.0)
+        .map(|(d, &count)| count * pairwise_error_probability(channel, d))
+        .sum()
+}
+
+// Union bound on the bit error probability, with input_weighted_spectrum[d] the total number of
+// message bits set over the codewords of weight d (see compute_input_weighted_spectrum)
+pub fn bit_error_union_bound(input_weighted_spectrum: &[f64], num_message_bits: usize, channel: Channel) -> f64 {
+    frame_error_union_bound(input_weighted_spectrum, channel) / num_message_bits as f64
+}
+
+// Union bound on the undetected error probability of a CRC-aided ML decoder: only error events
+// that the CRC does not detect count, so the undetected spectrum of the CRC (from
+// compute_undetected_spectrum) takes the place of the weight spectrum. The undetected spectrum
+// stops at max_search_distance, so the bound leaves out the heavier undetected ZTPs.
+pub fn undetected_error_union_bound(undetected_spectrum: &[u32], channel: Channel) -> f64 {
+    let spectrum: Vec<f64> = undetected_spectrum.iter().map(|&count| f64::from(count)).collect();
+    frame_error_union_bound(&spectrum, channel)
+}
+
+// For every output weight d, the total input weight of the message transitions over all ZTCC paths
+// of trellis_len sections from state 0 to state 0 with output weight d. These are the same paths
+// as in compute_ztcc_weight_spectrum; the last mu (termination) transitions carry no message
+// bits, so their inputs are not counted. This is sum_w w A(w, d) of the IOWE of compute_ztcc_iowe,
+// but in f64 and without the input weight dimension, so it is cheap for the whole spectrum.
+pub fn compute_input_weighted_spectrum(trellis_len: u16, trellis: &Trellis) -> Vec<f64> {
+
+    let num_states = trellis.num_states;
+    let trellis_len = usize::from(trellis_len);
+    let num_message_sections = trellis_len.saturating_sub(trellis.termination_length);
+    let n = trellis.num_output_symbols.trailing_zeros() as usize;
+    let max_weight = n * trellis_len;
+
+    // paths[state][d] and input_weights[state][d]: number of paths into state with output weight d,
+    // and their total input weight
+    let mut paths: Vec<Vec<f64>> = vec![vec![0.0; max_weight + 1]; num_states];
+    let mut input_weights: Vec<Vec<f64>> = vec![vec![0.0; max_weight + 1]; num_states];
+    paths[0][0] = 1.0;
+
+    for section in 0..trellis_len {
+        let mut next_paths: Vec<Vec<f64>> = vec![vec![0.0; max_weight + 1]; num_states];
+        let mut next_input_weights: Vec<Vec<f64>> = vec![vec![0.0; max_weight + 1]; num_states];
+        for state in 0..num_states {
+            for input in 0..trellis.num_input_symbols {
+                let next_state = *trellis.next_states.index(state, input);
+                let output_weight = trellis.outputs.index(state, input).count_ones() as usize;
+                let input_weight = if section < num_message_sections { input.count_ones() as f64 } else { 0.0 };
+                for d in 0..=(max_weight - output_weight) {
+                    let count = paths[state][d];
+                    if count == 0.0 {
+                        continue;
+                    }
+                    next_paths[next_state][d + output_weight] += count;
+                    next_input_weights[next_state][d + output_weight] += input_weights[state][d] + input_weight * count;
+                }
+            }
+        }
+        paths = next_paths;
+        input_weights = next_input_weights;
+    }
+
+    let mut spectrum = input_weights.swap_remove(0);
+    while spectrum.len() > 1 && spectrum.last() == Some(&0.0) {
+        spectrum.pop();
+    }
+    spectrum
+}
+
+// The input-weighted spectrum sum_w w A(w, d) of compute_input_weighted_spectrum, from an IOWE of
+// compute_ztcc_iowe (or read back from iowe.json), up to the output weights the IOWE holds
+pub fn input_weighted_spectrum_from_iowe(iowe: &[Vec<BigUint>]) -> Vec<f64> {
+    let num_weights = iowe.iter().map(|coefficients| coefficients.len()).max().unwrap_or(0);
+    (0..num_weights).map(|d| {
+        iowe.iter().enumerate()
+            .filter_map(|(w, coefficients)| coefficients.get(d).map(|count| w as f64 * count.to_f64().unwrap_or(f64::INFINITY)))
+            .sum()
+    }).collect()
+}
+
+// Q(x) = P(N(0, 1) > x) = erfc(x / sqrt(2)) / 2
+pub fn q_function(x: f64) -> f64 {
+    0.5 * erfc(x / std::f64::consts::SQRT_2)
+}
+
+// Complementary error function, with fractional error below 1.2e-7 everywhere
+// (the Chebyshev fit of Numerical Recipes)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let polynomial = -z * z - 1.26551223 + t * (1.00002368 + t * (0.37409196 + t * (0.09678418
+        + t * (-0.18628806 + t * (0.27886807 + t * (-1.13520398 + t * (1.48851587
+        + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * polynomial.exp();
+    if x >= 0.0 { result } else { 2.0 - result }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::union_bounds::{q_function, pairwise_error_probability, frame_error_union_bound, compute_input_weighted_spectrum, input_weighted_spectrum_from_iowe, Channel};
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::compute_ztcc_weight_spectrum::compute_ztcc_iowe;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+
+    #[test]
+    fn test_q_function() {
+        assert!((q_function(0.0) - 0.5).abs() < 1e-7);
+        assert!((q_function(1.0) - 0.158655254).abs() / 0.158655254 < 1e-6);
+        assert!((q_function(3.0) - 1.349898e-3).abs() / 1.349898e-3 < 1e-5);
+        assert!((q_function(-1.0) - 0.841344746).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_bsc_pairwise_error() {
+        // d = 1 and d = 2 both fail with probability p; d = 3 needs two or three flips
+        let p = 0.01;
+        let channel = Channel::Bsc { crossover: p };
+        assert!((pairwise_error_probability(channel, 1) - p).abs() < 1e-12);
+        assert!((pairwise_error_probability(channel, 2) - p).abs() < 1e-12);
+        let expected = 3.0 * p * p * (1.0 - p) + p * p * p;
+        assert!((pairwise_error_probability(channel, 3) - expected).abs() < 1e-12);
+
+        // a noiseless channel never errs, and one that flips every bit always does
+        assert_eq!(pairwise_error_probability(Channel::Bsc { crossover: 0.0 }, 3), 0.0);
+        assert_eq!(pairwise_error_probability(Channel::Bsc { crossover: 1.0 }, 3), 1.0);
+        assert_eq!(pairwise_error_probability(Channel::Bsc { crossover: 1.0 }, 2), 1.0);
+    }
+
+    #[test]
+    fn test_union_bound_approaches_dominant_term() {
+        // at high SNR the bound is dominated by the d_free term
+        let spectrum = [1.0, 0.0, 0.0, 0.0, 0.0, 6.0, 20.0, 60.0];
+        let channel = Channel::Awgn { eb_n0_db: 10.0, rate: 0.5 };
+        let dominant = 6.0 * pairwise_error_probability(channel, 5);
+        let bound = frame_error_union_bound(&spectrum, channel);
+        assert!(bound >= dominant && bound < 1.05 * dominant);
+    }
+
+    #[test]
+    fn test_input_weighted_spectrum_matches_enumeration() {
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        // 6 sections with 2 for the termination: 16 messages of 4 bits
+        let spectrum = compute_input_weighted_spectrum(6, &trellis);
+        let mut expected = vec![0.0; 13];
+        for m in 0..16_usize {
+            let message: BitVec = (0..4).rev().map(|j| (m >> j) & 1 == 1).collect();
+            expected[encode_ztcc(&message, &trellis).count_ones()] += m.count_ones() as f64;
+        }
+        while expected.last() == Some(&0.0) {
+            expected.pop();
+        }
+        assert_eq!(spectrum, expected);
+
+        // the same as the first moment of the IOWE in the input weight
+        let iowe = compute_ztcc_iowe(6, trellis, usize::MAX).unwrap();
+        assert_eq!(input_weighted_spectrum_from_iowe(&iowe), spectrum);
+    }
+}
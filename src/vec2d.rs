@@ -4,25 +4,14 @@
 // It provides a very nice 2d vector type which will be used throughout this project
 
 use std::fmt;
-use ::gf256::p16;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Vec2d<T> {
     pub vec: Vec<T>,
     pub row: usize,
     pub col: usize,
 }
 
-pub fn fill(fill: usize, len: usize) -> Vec<u16> {
-    let vec: Vec<u16> = (0..len).map(|_| { fill as u16 }).collect();
-    vec
-}
-
-pub fn fill_p16(fill: usize, len: usize) -> Vec<p16> {
-    let vec: Vec<p16> = (0..len).map(|_| { p16(fill as u16) }).collect();
-    vec
-}
-
 impl<T> Vec2d<T> {
     pub fn new(vec: Vec<T>, row: usize, col: usize) -> Self {
         assert!(vec.len() == row * col);
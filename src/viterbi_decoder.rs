@@ -0,0 +1,154 @@
+use crate::trellis::trellis::Trellis;
+
+use bitvec::prelude::*;
+
+// Hard-decision Viterbi decoding of a ZTCC codeword: the branch metric is the Hamming distance
+// between the received bits and the branch output. The received word holds n*(N + mu) bits in the
+// layout of encode_ztcc, and the decoded k*N input bits are returned (the termination is dropped).
+// The simulations use the CRC-aided list decoders, so nothing in main calls the plain Viterbi decoders.
+#[allow(dead_code)]
+pub fn viterbi_decode_hard(received: &BitSlice, trellis: &Trellis) -> BitVec {
+    let (num_sections, branch_metric) = hard_decision_metric(received, trellis);
+    viterbi_decode(num_sections, trellis, branch_metric)
+}
+
+// Soft-decision Viterbi decoding with BPSK observations or LLRs, one value per code bit in the
+// layout of encode_ztcc. Bit 0 is sent as +1 and bit 1 as -1, so a positive value favours 0 (the
+// sign convention of LLR = log P(0) / P(1)). Minimizing the Euclidean distance to the BPSK points
+// is the same as maximizing the correlation, so the branch metric adds up the values at the
+// positions where the branch output is 1.
+#[allow(dead_code)]
+pub fn viterbi_decode_soft(received: &[f64], trellis: &Trellis) -> BitVec {
+    let (num_sections, branch_metric) = soft_decision_metric(received, trellis);
+    viterbi_decode(num_sections, trellis, branch_metric)
+}
+
+// Number of trellis sections in a hard-decision received word and its branch metric(section, output)
+pub fn hard_decision_metric(received: &BitSlice, trellis: &Trellis) -> (usize, impl Fn(usize, u64) -> f64) {
+    let n = trellis.num_output_symbols.trailing_zeros() as usize;
+    assert_eq!(received.len() % n, 0, "received length {} is not a multiple of n = {}", received.len(), n);
+
+    let received_symbols: Vec<u64> = received.chunks(n)
+        .map(|section| section.iter().fold(0_u64, |symbol, bit| (symbol << 1) | u64::from(*bit)))
+        .collect();
+    (received_symbols.len(), move |section: usize, output: u64| {
+        f64::from((received_symbols[section] ^ output).count_ones())
+    })
+}
+
+// Number of trellis sections in a soft-decision received word and its branch metric(section, output)
+pub fn soft_decision_metric<'a>(received: &'a [f64], trellis: &Trellis) -> (usize, impl Fn(usize, u64) -> f64 + 'a) {
+    let n = trellis.num_output_symbols.trailing_zeros() as usize;
+    assert_eq!(received.len() % n, 0, "received length {} is not a multiple of n = {}", received.len(), n);
+
+    (received.len() / n, move |section: usize, output: u64| {
+        (0..n).filter(|j| (output >> (n - 1 - j)) & 1 == 1)
+            .map(|j| received[section * n + j])
+            .sum()
+    })
+}
+
+// Viterbi algorithm over num_sections trellis sections with start and end state 0, minimizing the
+// sum of branch_metric(section, output) along the path
+fn viterbi_decode(num_sections: usize, trellis: &Trellis, branch_metric: impl Fn(usize, u64) -> f64) -> BitVec {
+
+    let k = trellis.num_input_rails;
+    let num_states = trellis.num_states;
+    assert!(num_sections >= trellis.termination_length, "received word is shorter than the termination");
+
+    let mut path_metrics: Vec<f64> = vec![f64::INFINITY; num_states];
+    path_metrics[0] = 0.0;
+    // survivors[section][state] holds the (previous state, input) of the best path into state
+    let mut survivors: Vec<Vec<(usize, usize)>> = Vec::with_capacity(num_sections);
+
+    for section in 0..num_sections {
+        let mut next_metrics: Vec<f64> = vec![f64::INFINITY; num_states];
+        let mut section_survivors: Vec<(usize, usize)> = vec![(0, 0); num_states];
+        for (state, &metric) in path_metrics.iter().enumerate() {
+            if metric == f64::INFINITY {
+                continue;
+            }
+            for input in 0..trellis.num_input_symbols {
+                let next_state = *trellis.next_states.index(state, input);
+                let candidate = metric + branch_metric(section, *trellis.outputs.index(state, input));
+                if candidate < next_metrics[next_state] {
+                    next_metrics[next_state] = candidate;
+                    section_survivors[next_state] = (state, input);
+                }
+            }
+        }
+        path_metrics = next_metrics;
+        survivors.push(section_survivors);
+    }
+
+    // trace back from state 0
+    let mut inputs: Vec<usize> = vec![0; num_sections];
+    let mut state = 0;
+    for section in (0..num_sections).rev() {
+        let (pre_state, input) = survivors[section][state];
+        inputs[section] = input;
+        state = pre_state;
+    }
+
+    let num_message_sections = num_sections - trellis.termination_length;
+    let mut decoded: BitVec = BitVec::with_capacity(k * num_message_sections);
+    for &input in &inputs[..num_message_sections] {
+        for j in (0..k).rev() {
+            decoded.push((input >> j) & 1 == 1);
+        }
+    }
+
+    decoded
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::viterbi_decoder::{viterbi_decode_hard, viterbi_decode_soft};
+    use crate::encode_ztcc::encode_ztcc;
+    use crate::trellis::poly2trellis::poly2trellis;
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::vec2d::Vec2d;
+
+    use bitvec::prelude::*;
+
+    // a fixed pseudo-random message
+    fn message(len: usize) -> BitVec {
+        let mut x: u32 = 0x1f2e;
+        (0..len).map(|_| { x ^= x << 13; x ^= x >> 17; x ^= x << 5; x & 1 == 1 }).collect()
+    }
+
+    #[test]
+    fn test_hard_decision_corrects_errors() {
+        // d_free = 5 corrects any two errors in an error event
+        let trellis = poly2trellis(&[3], &Vec2d::new(vec![7, 5], 1, 2)).unwrap();
+        let message = message(40);
+        let mut received = encode_ztcc(&message, &trellis);
+        assert_eq!(viterbi_decode_hard(&received, &trellis), message);
+        for position in [3, 5, 50, 81] {
+            let flipped = !received[position];
+            received.set(position, flipped);
+        }
+        assert_eq!(viterbi_decode_hard(&received, &trellis), message);
+    }
+
+    #[test]
+    fn test_soft_decision_feedback_code() {
+        let trellis = generate_feedback_trellis(7, &[107, 135, 133], 141).unwrap();
+        let message = message(3 * 30);
+        let codeword = encode_ztcc(&message, &trellis);
+        // noisy BPSK: every fifth symbol is pushed close to the decision boundary, one is flipped
+        let mut received: Vec<f64> = codeword.iter().enumerate()
+            .map(|(i, bit)| {
+                let symbol = if *bit { -1.0 } else { 1.0 };
+                if i % 5 == 0 { 0.1 * symbol } else { symbol }
+            })
+            .collect();
+        received[17] = -received[17];
+        assert_eq!(viterbi_decode_soft(&received, &trellis), message);
+        // the hard decisions of the same observations still decode
+        let hard: BitVec = received.iter().map(|&y| y < 0.0).collect();
+        assert_eq!(viterbi_decode_hard(&hard, &trellis), message);
+    }
+}
@@ -0,0 +1,249 @@
+use crate::trellis::trellis::{TrellisError, binary_to_octal, reverse_bits};
+use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+use crate::trellis::catastrophic::find_zero_output_cycle;
+use crate::trellis::distance::find_free_distance;
+use crate::compute_ztcc_weight_spectrum::compute_ztcc_weight_spectrum;
+
+use num_bigint::BigUint;
+
+use rayon::prelude::*;
+
+use serde::{Deserialize, Serialize};
+
+use std::cmp::Ordering;
+
+// The search enumerates tuples of all 2^v polynomials, which is out of reach well before 2^32
+const MAX_SEARCH_V: u16 = 31;
+
+// A feedback encoder found by ztcc_code_search, in the octal convention of generate_feedback_trellis
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeCandidate {
+    pub numerators: Vec<u64>,
+    pub denominator: u64,
+    pub free_distance: u32,
+    pub multiplicity: u64,
+    #[serde(with = "crate::compute_ztcc_weight_spectrum::exact_spectrum")]
+    pub weight_spectrum: Vec<BigUint>,
+}
+
+//   This function searches all rate-k/(k+1) feedback encoders with v memory elements
+//   (v taps per polynomial) for the best ZTCC distance spectrum.
+
+//   An encoder computes the parity p from h_1 u_1 + ... + h_k u_k = q p, so a zero-terminated
+//   codeword is any solution of h_1 u_1 + ... + h_k u_k + q p = 0. Two encoders give the same
+//   weight spectrum when
+//       1) their polynomials {h_1, ..., h_k, q} are a permutation of each other, since that only
+//          permutes the codeword bits (any delay-free polynomial can serve as the denominator), or
+//       2) one uses the reciprocal polynomials of the other, which reverses every codeword in time.
+//   Only one encoder per class is kept, with distinct numerators and the largest delay-free
+//   polynomial as the denominator. Encoders with fewer than v memory elements and encoders whose
+//   trellis has a zero-output cycle (catastrophic or non-minimal) are discarded.
+
+//   Inputs:
+//       1) v: the number of taps of every polynomial (2^(v-1) states)
+//       2) k: the number of input rails
+//       3) trellis_len: the trellis length N used for the weight spectrum
+//       4) num_candidates: how many encoders to return
+
+//   Outputs: the num_candidates best encoders, ranked by decreasing free distance and then by the
+//   weight spectrum of compute_ztcc_weight_spectrum (fewer codewords at the lowest differing weight first).
+//   Only the num_candidates encoders with the fewest error events at the free distance (and those tied
+//   with the last of them) get a full weight spectrum, so the ranking is among that shortlist.
+//   UnsupportedMemory is returned for a v outside of the searchable range.
+pub fn ztcc_code_search(v: u16, k: usize, trellis_len: u16, num_candidates: usize) -> Result<Vec<CodeCandidate>, TrellisError> {
+
+    let max_v = MAX_SEARCH_V;
+    if v < 2 || v > max_v {
+        return Err(TrellisError::UnsupportedMemory { v, max_v });
+    }
+    let num_polynomials: u64 = 1 << v;
+    let top_tap: u64 = 1 << (v - 1);
+
+    // every strictly increasing (k+1)-tuple of nonzero polynomials, split by its smallest element
+    // so that rayon can share out the work
+    let mut candidates: Vec<CodeCandidate> = (1..num_polynomials).into_par_iter()
+        .flat_map_iter(|first| {
+            let mut found: Vec<CodeCandidate> = vec![];
+            let mut tuple: Vec<u64> = vec![first];
+            extend_tuples(&mut tuple, k + 1, num_polynomials, &mut |polynomials| {
+                if let Some(candidate) = evaluate_encoder(v, top_tap, polynomials) {
+                    found.push(candidate);
+                }
+            });
+            found
+        })
+        .collect();
+
+    shortlist(&mut candidates, num_candidates);
+
+    for candidate in candidates.iter_mut() {
+        let trellis = generate_feedback_trellis(v, &candidate.numerators, candidate.denominator)
+            .expect("couldn't generate the trellis");
+        candidate.weight_spectrum = compute_ztcc_weight_spectrum(trellis_len, trellis).unwrap();
+    }
+
+    candidates.sort_by(|a, b| b.free_distance.cmp(&a.free_distance)
+        .then(compare_spectra(&a.weight_spectrum, &b.weight_spectrum))
+        .then(a.denominator.cmp(&b.denominator))
+        .then(a.numerators.cmp(&b.numerators)));
+    candidates.truncate(num_candidates);
+
+    Ok(candidates)
+}
+
+// Keeps the num_candidates encoders with the largest free distance and the fewest error events at
+// it, together with every encoder that ties with the last one kept, since their weight spectra
+// have yet to break the tie
+fn shortlist(candidates: &mut Vec<CodeCandidate>, num_candidates: usize) {
+    candidates.sort_by(|a, b| b.free_distance.cmp(&a.free_distance)
+        .then(a.multiplicity.cmp(&b.multiplicity))
+        .then(a.denominator.cmp(&b.denominator))
+        .then(a.numerators.cmp(&b.numerators)));
+    if num_candidates == 0 || candidates.len() <= num_candidates {
+        candidates.truncate(num_candidates);
+        return;
+    }
+    let last = &candidates[num_candidates - 1];
+    let (free_distance, multiplicity) = (last.free_distance, last.multiplicity);
+    let num_kept = num_candidates + candidates[num_candidates..].iter()
+        .take_while(|candidate| candidate.free_distance == free_distance && candidate.multiplicity == multiplicity)
+        .count();
+    candidates.truncate(num_kept);
+}
+
+// Calls on_tuple with every strictly increasing tuple of polynomials below num_polynomials that
+// starts with the given prefix and has length tuple_len
+fn extend_tuples(tuple: &mut Vec<u64>, tuple_len: usize, num_polynomials: u64, on_tuple: &mut dyn FnMut(&[u64])) {
+    if tuple.len() == tuple_len {
+        on_tuple(tuple);
+        return;
+    }
+    for next in (tuple[tuple.len() - 1] + 1)..num_polynomials {
+        tuple.push(next);
+        extend_tuples(tuple, tuple_len, num_polynomials, on_tuple);
+        tuple.pop();
+    }
+}
+
+// Builds the class representative of a sorted tuple of binary polynomials, or None if the tuple
+// is not the canonical member of its class, has fewer than v memory elements, or is catastrophic
+fn evaluate_encoder(v: u16, top_tap: u64, polynomials: &[u64]) -> Option<CodeCandidate> {
+
+    if polynomials.iter().all(|&polynomial| polynomial & top_tap == 0) {
+        return None;
+    }
+
+    // keep the tuple whose sorted reciprocal is not smaller
+    let mut reciprocals: Vec<u64> = polynomials.iter().map(|&polynomial| reverse_bits(polynomial, v)).collect();
+    reciprocals.sort_unstable();
+    if reciprocals.as_slice() < polynomials {
+        return None;
+    }
+
+    let denominator_index = polynomials.iter().rposition(|&polynomial| polynomial & 1 == 1)?;
+    let denominator = binary_to_octal(polynomials[denominator_index]);
+    let numerators: Vec<u64> = polynomials.iter().enumerate()
+        .filter(|&(index, _)| index != denominator_index)
+        .map(|(_, &polynomial)| binary_to_octal(polynomial))
+        .collect();
+
+    let trellis = generate_feedback_trellis(v, &numerators, denominator).ok()?;
+    if find_zero_output_cycle(&trellis).is_some() {
+        return None;
+    }
+    let free_distance = find_free_distance(&trellis);
+
+    Some(CodeCandidate {
+        numerators,
+        denominator,
+        free_distance: free_distance.free_distance,
+        multiplicity: free_distance.multiplicity?,
+        weight_spectrum: vec![],
+    })
+}
+
+// Orders weight spectra so that the one with fewer codewords at the lowest differing weight comes first
+fn compare_spectra(a: &[BigUint], b: &[BigUint]) -> Ordering {
+    let zero = BigUint::from(0_u32);
+    let len = a.len().max(b.len());
+    for weight in 1..len {
+        let count_a = a.get(weight).unwrap_or(&zero);
+        let count_b = b.get(weight).unwrap_or(&zero);
+        match count_a.cmp(count_b) {
+            Ordering::Equal => continue,
+            ordering => return ordering,
+        }
+    }
+    Ordering::Equal
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::ztcc_code_search::{ztcc_code_search, shortlist, CodeCandidate};
+    use crate::trellis::generate_feedback_trellis::generate_feedback_trellis;
+    use crate::compute_ztcc_weight_spectrum::compute_ztcc_weight_spectrum;
+    use crate::trellis::trellis::TrellisError;
+
+    use num_bigint::BigUint;
+
+    #[test]
+    fn test_equivalent_encoders_share_spectrum() {
+        // {11, 13, 17} in octal with each polynomial as the denominator, and its reciprocal set {11, 15, 17}
+        let spectrum = |numerators: &[u64], denominator: u64| {
+            let trellis = generate_feedback_trellis(4, numerators, denominator).unwrap();
+            compute_ztcc_weight_spectrum(6, trellis).unwrap()
+        };
+        let reference = spectrum(&[11, 17], 13);
+        assert_eq!(spectrum(&[17, 11], 13), reference);
+        assert_eq!(spectrum(&[11, 13], 17), reference);
+        assert_eq!(spectrum(&[13, 17], 11), reference);
+        assert_eq!(spectrum(&[11, 17], 15), reference);
+    }
+
+    #[test]
+    fn test_search_rate_1_2() {
+        // with v = 3 taps the best rate-1/2 feedback encoder has the (7, 5) free distance of 5
+        let candidates = ztcc_code_search(3, 1, 8, 4).unwrap();
+        assert!(!candidates.is_empty());
+        assert_eq!(candidates[0].free_distance, 5);
+        assert!(candidates.windows(2).all(|pair| pair[0].free_distance >= pair[1].free_distance));
+        assert_eq!(candidates[0].weight_spectrum[5], BigUint::from(8_u32 - 3 + 1));
+    }
+
+    #[test]
+    fn test_shortlist_keeps_ties_at_cutoff() {
+        let candidate = |denominator: u64, free_distance: u32, multiplicity: u64| CodeCandidate {
+            numerators: vec![5],
+            denominator,
+            free_distance,
+            multiplicity,
+            weight_spectrum: vec![],
+        };
+        let mut candidates = vec![candidate(7, 5, 2), candidate(13, 6, 1), candidate(15, 5, 2), candidate(17, 5, 1), candidate(11, 4, 1)];
+        shortlist(&mut candidates, 2);
+        let kept: Vec<u64> = candidates.iter().map(|candidate| candidate.denominator).collect();
+        assert_eq!(kept, vec![13, 17]);
+        // the third place is a tie between 7 and 15, so both are kept
+        let mut candidates = vec![candidate(7, 5, 2), candidate(13, 6, 1), candidate(15, 5, 2), candidate(17, 5, 1), candidate(11, 4, 1)];
+        shortlist(&mut candidates, 3);
+        let kept: Vec<u64> = candidates.iter().map(|candidate| candidate.denominator).collect();
+        assert_eq!(kept, vec![13, 17, 7, 15]);
+    }
+
+    #[test]
+    fn test_search_result_does_not_depend_on_cutoff() {
+        // the best encoder by weight spectrum is the same however short the list
+        let all = ztcc_code_search(3, 1, 8, usize::MAX).unwrap();
+        let best = ztcc_code_search(3, 1, 8, 1).unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!((&best[0].numerators, best[0].denominator), (&all[0].numerators, all[0].denominator));
+    }
+
+    #[test]
+    fn test_unsupported_memory() {
+        assert_eq!(ztcc_code_search(1, 1, 8, 4).err(), Some(TrellisError::UnsupportedMemory { v: 1, max_v: 31 }));
+        assert_eq!(ztcc_code_search(40, 1, 8, 4).err(), Some(TrellisError::UnsupportedMemory { v: 40, max_v: 31 }));
+    }
+}